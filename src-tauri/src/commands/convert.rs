@@ -1,17 +1,116 @@
 use log::info;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use tauri::{AppHandle, Emitter};
 
-use super::ffmpeg_utils::{get_ffmpeg_path, get_ffprobe_path};
+use super::ffmpeg_utils::{
+    get_ffmpeg_path, get_ffprobe_path, parse_ffmpeg_time, run_ffmpeg_analysis,
+    run_ffmpeg_with_progress,
+};
+use super::loudness::{prepare_normalization, LoudnessTarget, MEASURE_PHASE_WEIGHT};
+use super::media_info::{probe_media_info, StreamKind};
+use super::video::{get_video_info, TimeRange};
 
 lazy_static::lazy_static! {
     static ref CONVERT_CANCELLED: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
     static ref CONVERT_PROCESS: Arc<Mutex<Option<u32>>> = Arc::new(Mutex::new(None));
 }
 
+// Opus 编码器只支持这几种采样率
+const OPUS_SAMPLE_RATES: [u32; 5] = [48_000, 24_000, 16_000, 12_000, 8_000];
+// 按目标大小编码时的音频码率
+const TARGET_SIZE_AUDIO_KBPS: u32 = 128;
+// 给封装开销和码率波动留出余量
+const TARGET_SIZE_MARGIN: f64 = 0.97;
+// 低于该视频码率画面基本不可用，直接提示目标过小
+const MIN_TARGET_VIDEO_KBPS: u32 = 100;
+const MIN_OUTPUT_DIMENSION: u32 = 16;
+const MAX_OUTPUT_DIMENSION: u32 = 8192;
+const MAX_OUTPUT_FPS: f64 = 240.0;
+
+/// 音频导出格式，`aac` 为 ADTS 裸流，`m4a` 为 MP4 容器
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AudioFormat {
+    Mp3,
+    Aac,
+    M4a,
+    Wav,
+    Flac,
+    Opus,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AudioExtractResult {
+    pub output_path: String,
+    /// 是否直接复制音频流（未重新编码）
+    pub stream_copy: bool,
+}
+
+/// 源编码能否不经转码直接放进目标格式
+fn audio_copy_compatible(format: AudioFormat, codec: &str) -> bool {
+    match format {
+        AudioFormat::Mp3 => codec == "mp3",
+        AudioFormat::Aac | AudioFormat::M4a => codec == "aac",
+        // WAV 只能装小端 PCM，MOV/AIFF 里常见的大端 PCM 需要转码
+        AudioFormat::Wav => matches!(
+            codec,
+            "pcm_u8" | "pcm_s16le" | "pcm_s24le" | "pcm_s32le" | "pcm_f32le" | "pcm_f64le"
+        ),
+        AudioFormat::Flac => codec == "flac",
+        AudioFormat::Opus => codec == "opus",
+    }
+}
+
+/// 生成音频编码参数，返回参数和是否直接复制
+///
+/// 指定了码率或与源不同的采样率时一律重新编码。
+fn build_audio_codec_args(
+    format: AudioFormat,
+    source_codec: Option<&str>,
+    source_sample_rate: Option<u32>,
+    bitrate_kbps: Option<u32>,
+    sample_rate: Option<u32>,
+) -> Result<(Vec<String>, bool), String> {
+    if let Some(rate) = sample_rate {
+        if format == AudioFormat::Opus && !OPUS_SAMPLE_RATES.contains(&rate) {
+            return Err("Opus 只支持 48000、24000、16000、12000、8000 Hz 采样率".into());
+        }
+    }
+
+    let keeps_sample_rate = sample_rate.is_none() || sample_rate == source_sample_rate;
+    if bitrate_kbps.is_none()
+        && keeps_sample_rate
+        && source_codec.is_some_and(|codec| audio_copy_compatible(format, codec))
+    {
+        return Ok((vec!["-c:a".to_string(), "copy".to_string()], true));
+    }
+
+    let (encoder, default_bitrate) = match format {
+        AudioFormat::Mp3 => ("libmp3lame", Some(192)),
+        AudioFormat::Aac | AudioFormat::M4a => ("aac", Some(192)),
+        AudioFormat::Wav => ("pcm_s16le", None),
+        AudioFormat::Flac => ("flac", None),
+        AudioFormat::Opus => ("libopus", Some(128)),
+    };
+    let mut args = vec!["-c:a".to_string(), encoder.to_string()];
+    // 无损格式忽略码率
+    if let Some(default_bitrate) = default_bitrate {
+        args.push("-b:a".to_string());
+        args.push(format!("{}k", bitrate_kbps.unwrap_or(default_bitrate)));
+    }
+    if let Some(rate) = sample_rate {
+        args.push("-ar".to_string());
+        args.push(rate.to_string());
+    }
+    Ok((args, false))
+}
+
 /// 获取视频时长
 fn get_duration(app: &AppHandle, path: &str) -> Result<f64, String> {
     let ffprobe = get_ffprobe_path(app);
@@ -34,7 +133,501 @@ fn get_duration(app: &AppHandle, path: &str) -> Result<f64, String> {
         .map_err(|e| format!("解析时长失败: {}", e))
 }
 
+/// 视频编码，未指定时 WebM 用 VP9，其余用 H.264
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum VideoCodec {
+    H264,
+    Hevc,
+    Vp9,
+    Av1,
+}
+
+impl VideoCodec {
+    fn default_for(container: &str) -> Self {
+        if container == "webm" {
+            Self::Vp9
+        } else {
+            Self::H264
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::H264 => "H.264",
+            Self::Hevc => "HEVC",
+            Self::Vp9 => "VP9",
+            Self::Av1 => "AV1",
+        }
+    }
+
+    /// 可用的编码器，按优先顺序排列
+    fn encoders(self) -> &'static [&'static str] {
+        match self {
+            Self::H264 => &["libx264"],
+            Self::Hevc => &["libx265"],
+            Self::Vp9 => &["libvpx-vp9"],
+            Self::Av1 => &["libsvtav1", "libaom-av1"],
+        }
+    }
+
+    fn supports_container(self, container: &str) -> bool {
+        match container {
+            "mp4" | "mkv" => true,
+            "mov" => matches!(self, Self::H264 | Self::Hevc),
+            "webm" => matches!(self, Self::Vp9 | Self::Av1),
+            _ => false,
+        }
+    }
+
+    /// WebM 只能用 Opus，VP9/AV1 放进 MKV 时也用 Opus，其余用 AAC
+    fn audio_args(self, container: &str) -> [&'static str; 4] {
+        let use_opus =
+            container == "webm" || (container == "mkv" && matches!(self, Self::Vp9 | Self::Av1));
+        if use_opus {
+            ["-c:a", "libopus", "-b:a", "128k"]
+        } else {
+            ["-c:a", "aac", "-b:a", "192k"]
+        }
+    }
+}
+
+/// 各编码器按画质对应的 CRF 和速度参数（CRF 越小质量越高）
+fn video_encoder_args(encoder: &str, quality: &str, container: &str) -> Vec<String> {
+    // 依次为 high、medium、low 和默认画质
+    let crf = |values: [u8; 4]| {
+        let value = match quality {
+            "high" => values[0],
+            "medium" => values[1],
+            "low" => values[2],
+            _ => values[3],
+        };
+        value.to_string()
+    };
+
+    let mut args = vec!["-c:v".to_string(), encoder.to_string()];
+    let tuning: Vec<String> = match encoder {
+        "libx265" => vec![
+            "-crf".into(),
+            crf([22, 28, 32, 24]),
+            "-preset".into(),
+            "fast".into(),
+        ],
+        "libvpx-vp9" => vec![
+            "-crf".into(),
+            crf([24, 31, 37, 28]),
+            "-b:v".into(),
+            "0".into(),
+            "-deadline".into(),
+            "good".into(),
+            "-cpu-used".into(),
+            "4".into(),
+            "-row-mt".into(),
+            "1".into(),
+        ],
+        "libsvtav1" => vec![
+            "-crf".into(),
+            crf([26, 35, 45, 30]),
+            "-preset".into(),
+            "8".into(),
+        ],
+        "libaom-av1" => vec![
+            "-crf".into(),
+            crf([24, 32, 40, 28]),
+            "-b:v".into(),
+            "0".into(),
+            "-cpu-used".into(),
+            "6".into(),
+            "-row-mt".into(),
+            "1".into(),
+        ],
+        _ => vec![
+            "-crf".into(),
+            crf([18, 23, 28, 20]),
+            "-preset".into(),
+            "fast".into(),
+        ],
+    };
+    args.extend(tuning);
+    args.extend(["-pix_fmt".to_string(), "yuv420p".to_string()]);
+    // 苹果设备只认 hvc1 标签的 HEVC
+    if encoder == "libx265" && matches!(container, "mp4" | "mov") {
+        args.extend(["-tag:v".to_string(), "hvc1".to_string()]);
+    }
+    args
+}
+
+/// 解析 `ffmpeg -encoders` 的输出，返回编码器名称
+fn parse_encoder_list(output: &str) -> HashSet<String> {
+    output
+        .lines()
+        .skip_while(|line| !line.trim_start().starts_with("------"))
+        .skip(1)
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let flags = parts.next()?;
+            (flags.len() == 6).then(|| parts.next()).flatten()
+        })
+        .map(str::to_string)
+        .collect()
+}
+
+fn available_encoders(ffmpeg: &Path) -> Result<HashSet<String>, String> {
+    let output = Command::new(ffmpeg)
+        .args(["-hide_banner", "-encoders"])
+        .output()
+        .map_err(|e| format!("执行 ffmpeg 失败: {}", e))?;
+    Ok(parse_encoder_list(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// 选出内置 ffmpeg 实际支持的编码器，音频编码器不可用时同样报错
+fn select_encoder(
+    codec: VideoCodec,
+    container: &str,
+    available: &HashSet<String>,
+) -> Result<&'static str, String> {
+    let encoder = codec
+        .encoders()
+        .iter()
+        .copied()
+        .find(|name| available.contains(*name))
+        .ok_or_else(|| {
+            format!(
+                "当前 ffmpeg 不支持 {} 编码（需要 {}）",
+                codec.label(),
+                codec.encoders().join(" 或 ")
+            )
+        })?;
+    let audio_encoder = codec.audio_args(container)[1];
+    if !available.contains(audio_encoder) {
+        return Err(format!("当前 ffmpeg 不支持 {} 音频编码", audio_encoder));
+    }
+    Ok(encoder)
+}
+
+/// 同时指定宽高时的缩放方式：`fit` 完整显示，`fill` 铺满并裁掉多余部分，`pad` 完整显示并补黑边
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScaleMode {
+    #[default]
+    Fit,
+    Fill,
+    Pad,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Rotation {
+    Clockwise,
+    CounterClockwise,
+    UpsideDown,
+}
+
+/// 裁剪区域（像素），按显示方向的原始画面计算
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+pub struct CropRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// 画面变换，依次为裁剪、旋转/翻转、缩放、帧率，合成一条滤镜链
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct VideoTransform {
+    pub crop: Option<CropRect>,
+    pub rotate: Option<Rotation>,
+    pub flip_horizontal: bool,
+    pub flip_vertical: bool,
+    /// 只给宽或高时按原比例计算另一边
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub scale_mode: ScaleMode,
+    pub fps: Option<f64>,
+}
+
+impl VideoTransform {
+    /// 按源画面尺寸（显示方向）校验并生成滤镜，不需要变换时返回空列表
+    fn build_filters(&self, source_width: u32, source_height: u32) -> Result<Vec<String>, String> {
+        if source_width == 0 || source_height == 0 {
+            return Err("文件中没有视频画面".into());
+        }
+
+        let mut filters = Vec::new();
+        if let Some(crop) = self.crop {
+            if crop.width < MIN_OUTPUT_DIMENSION || crop.height < MIN_OUTPUT_DIMENSION {
+                return Err(format!("裁剪区域不能小于 {0}x{0}", MIN_OUTPUT_DIMENSION));
+            }
+            // 用户输入可能很大，相加溢出同样视为超出范围
+            let fits = |offset: u32, size: u32, limit: u32| {
+                offset.checked_add(size).is_some_and(|end| end <= limit)
+            };
+            if !fits(crop.x, crop.width, source_width) || !fits(crop.y, crop.height, source_height)
+            {
+                return Err(format!(
+                    "裁剪区域超出画面范围（{}x{}）",
+                    source_width, source_height
+                ));
+            }
+            // yuv420p 要求宽高为偶数
+            filters.push(format!(
+                "crop={}:{}:{}:{}",
+                crop.width & !1,
+                crop.height & !1,
+                crop.x,
+                crop.y
+            ));
+        }
+
+        match self.rotate {
+            Some(Rotation::Clockwise) => filters.push("transpose=clock".into()),
+            Some(Rotation::CounterClockwise) => filters.push("transpose=cclock".into()),
+            Some(Rotation::UpsideDown) => filters.extend(["hflip".into(), "vflip".into()]),
+            None => {}
+        }
+        if self.flip_horizontal {
+            filters.push("hflip".into());
+        }
+        if self.flip_vertical {
+            filters.push("vflip".into());
+        }
+
+        for size in [self.width, self.height].into_iter().flatten() {
+            if !(MIN_OUTPUT_DIMENSION..=MAX_OUTPUT_DIMENSION).contains(&size) || size % 2 != 0 {
+                return Err(format!(
+                    "目标宽高必须是 {} 到 {} 之间的偶数",
+                    MIN_OUTPUT_DIMENSION, MAX_OUTPUT_DIMENSION
+                ));
+            }
+        }
+        match (self.width, self.height, self.scale_mode) {
+            (Some(width), Some(height), ScaleMode::Fit) => filters.push(format!(
+                "scale={}:{}:force_original_aspect_ratio=decrease:force_divisible_by=2",
+                width, height
+            )),
+            (Some(width), Some(height), ScaleMode::Fill) => filters.extend([
+                format!(
+                    "scale={}:{}:force_original_aspect_ratio=increase",
+                    width, height
+                ),
+                format!("crop={}:{}", width, height),
+            ]),
+            (Some(width), Some(height), ScaleMode::Pad) => filters.extend([
+                format!(
+                    "scale={}:{}:force_original_aspect_ratio=decrease:force_divisible_by=2",
+                    width, height
+                ),
+                format!("pad={}:{}:(ow-iw)/2:(oh-ih)/2", width, height),
+            ]),
+            (Some(width), None, _) => filters.push(format!("scale={}:-2", width)),
+            (None, Some(height), _) => filters.push(format!("scale=-2:{}", height)),
+            (None, None, _) => {}
+        }
+        if self.width.is_some() || self.height.is_some() {
+            filters.push("setsar=1".into());
+        }
+
+        if let Some(fps) = self.fps {
+            if !(fps > 0.0 && fps <= MAX_OUTPUT_FPS) {
+                return Err(format!("帧率必须在 0 到 {} 之间", MAX_OUTPUT_FPS));
+            }
+            filters.push(format!("fps={}", fps));
+        }
+        Ok(filters)
+    }
+}
+
+/// 按目标大小编码的结果，用于核对实际大小
+#[derive(Debug, Clone, Serialize)]
+pub struct TargetSizeReport {
+    pub output_path: String,
+    pub target_bytes: u64,
+    pub actual_bytes: u64,
+    pub video_bitrate_kbps: u32,
+    pub audio_bitrate_kbps: u32,
+}
+
+/// 两遍编码的统计文件放在系统临时目录，结束后整体删除
+struct PassLogDir {
+    path: PathBuf,
+}
+
+impl PassLogDir {
+    fn create() -> Result<Self, String> {
+        let unique = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        let path =
+            std::env::temp_dir().join(format!("convert_2pass_{}_{}", std::process::id(), unique));
+        std::fs::create_dir_all(&path).map_err(|e| format!("创建临时目录失败: {}", e))?;
+        Ok(Self { path })
+    }
+
+    fn prefix(&self) -> String {
+        self.path.join("ffmpeg2pass").to_string_lossy().to_string()
+    }
+}
+
+impl Drop for PassLogDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+/// 由目标大小、时长和音频码率算出视频码率（kbps）
+fn target_video_bitrate_kbps(
+    target_bytes: u64,
+    duration: f64,
+    audio_kbps: u32,
+) -> Result<u32, String> {
+    if duration <= 0.0 {
+        return Err("无法读取视频时长".into());
+    }
+    let total_kbps = target_bytes as f64 * 8.0 / 1000.0 * TARGET_SIZE_MARGIN / duration;
+    let video_kbps = total_kbps - audio_kbps as f64;
+    if video_kbps < MIN_TARGET_VIDEO_KBPS as f64 {
+        let min_bytes = (MIN_TARGET_VIDEO_KBPS + audio_kbps) as f64 * 1000.0 / 8.0 * duration
+            / TARGET_SIZE_MARGIN;
+        return Err(format!(
+            "目标大小过小，{:.0} 秒的视频至少需要 {:.1} MB",
+            duration,
+            min_bytes / 1024.0 / 1024.0
+        ));
+    }
+    Ok(video_kbps as u32)
+}
+
+/// 一次按目标大小的编码：`input` 时长 `duration` 秒，输出不超过 `target_bytes`
+struct TargetSizeJob<'a> {
+    input: &'a str,
+    output: &'a str,
+    duration: f64,
+    target_bytes: u64,
+    video_filter: Option<&'a str>,
+    audio_filter: Option<&'a str>,
+}
+
+/// 按目标大小做两遍 libx264 编码，取消时终止正在运行的那一遍
+fn run_target_size_encode<F>(
+    ffmpeg: &Path,
+    job: TargetSizeJob,
+    cancelled: &AtomicBool,
+    mut on_progress: F,
+) -> Result<TargetSizeReport, String>
+where
+    F: FnMut(f64),
+{
+    let TargetSizeJob {
+        input,
+        output,
+        duration,
+        target_bytes,
+        video_filter,
+        audio_filter,
+    } = job;
+    let video_kbps = target_video_bitrate_kbps(target_bytes, duration, TARGET_SIZE_AUDIO_KBPS)?;
+    let pass_log = PassLogDir::create()?;
+    let video_args = |pass: &str| {
+        let mut args = vec![
+            "-y".to_string(),
+            "-i".to_string(),
+            input.to_string(),
+            "-threads".to_string(),
+            "0".to_string(),
+            "-map".to_string(),
+            "0:v:0".to_string(),
+            "-c:v".to_string(),
+            "libx264".to_string(),
+            "-b:v".to_string(),
+            format!("{}k", video_kbps),
+            "-preset".to_string(),
+            "fast".to_string(),
+            "-pix_fmt".to_string(),
+            "yuv420p".to_string(),
+            "-pass".to_string(),
+            pass.to_string(),
+            "-passlogfile".to_string(),
+            pass_log.prefix(),
+        ];
+        if let Some(filter) = video_filter {
+            args.push("-vf".to_string());
+            args.push(filter.to_string());
+        }
+        args
+    };
+    info!(
+        "[转换] 目标大小 {:.1} MB，视频码率 {}k，音频码率 {}k",
+        target_bytes as f64 / 1024.0 / 1024.0,
+        video_kbps,
+        TARGET_SIZE_AUDIO_KBPS
+    );
+
+    let mut first_pass = video_args("1");
+    first_pass.extend(["-nostats".to_string(), "-an".to_string()]);
+    run_ffmpeg_analysis(
+        ffmpeg,
+        &first_pass,
+        duration,
+        cancelled,
+        &CONVERT_PROCESS,
+        |progress| on_progress(progress / 2.0),
+    )?;
+
+    let mut second_pass = video_args("2");
+    second_pass.extend([
+        "-map".to_string(),
+        "0:a:0?".to_string(),
+        "-c:a".to_string(),
+        "aac".to_string(),
+        "-b:a".to_string(),
+        format!("{}k", TARGET_SIZE_AUDIO_KBPS),
+    ]);
+    if let Some(filter) = audio_filter {
+        second_pass.push("-af".to_string());
+        second_pass.push(filter.to_string());
+    }
+    run_ffmpeg_with_progress(
+        ffmpeg,
+        &second_pass,
+        output,
+        duration,
+        cancelled,
+        &CONVERT_PROCESS,
+        |progress| on_progress(50.0 + progress / 2.0),
+    )?;
+
+    let actual_bytes = std::fs::metadata(output)
+        .map_err(|e| format!("输出文件不存在: {}", e))?
+        .len();
+    Ok(TargetSizeReport {
+        output_path: output.to_string(),
+        target_bytes,
+        actual_bytes,
+        video_bitrate_kbps: video_kbps,
+        audio_bitrate_kbps: TARGET_SIZE_AUDIO_KBPS,
+    })
+}
+
+/// `convert_video` 的可选设置，所有字段均可省略
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ConvertOptions {
+    /// 先测量整段响度，再按两遍标准化输出音频（GIF 忽略）
+    pub normalize_loudness: Option<LoudnessTarget>,
+    /// 按目标大小两遍编码（仅 H.264），此时忽略 `quality`
+    pub target_size_mb: Option<f64>,
+    /// 未指定时 WebM 用 VP9、其余用 H.264
+    pub video_codec: Option<VideoCodec>,
+    /// 裁剪、旋转、缩放和调整帧率，开始前按视频信息校验
+    pub transform: VideoTransform,
+}
+
 /// 转换视频格式
+///
+/// `format` 为 mp4、mov、mkv、webm 或 gif，其余设置见 [`ConvertOptions`]。开始前会确认
+/// 内置 ffmpeg 支持所选的编码器；按目标大小转换时实际大小通过 `convert-size-report` 事件上报。
 #[tauri::command]
 pub async fn convert_video(
     app: AppHandle,
@@ -42,31 +635,123 @@ pub async fn convert_video(
     output: String,
     format: String,
     quality: String,
+    options: Option<ConvertOptions>,
 ) -> Result<String, String> {
+    let ConvertOptions {
+        normalize_loudness,
+        target_size_mb,
+        video_codec,
+        transform,
+    } = options.unwrap_or_default();
+    if !matches!(format.as_str(), "mp4" | "mov" | "mkv" | "webm" | "gif") {
+        return Err(format!("不支持的输出格式: {}", format));
+    }
+    let codec = video_codec.unwrap_or_else(|| VideoCodec::default_for(&format));
+    if format != "gif" && !codec.supports_container(&format) {
+        return Err(format!(
+            "{} 格式不支持 {} 编码",
+            format.to_uppercase(),
+            codec.label()
+        ));
+    }
+    if target_size_mb.is_some() && format != "gif" && codec != VideoCodec::H264 {
+        return Err("按目标大小转换目前只支持 H.264 编码".into());
+    }
+    let target_bytes = match target_size_mb {
+        Some(_) if format == "gif" => return Err("GIF 不支持按目标大小转换".into()),
+        Some(megabytes) if megabytes.is_finite() && megabytes > 0.0 => {
+            Some((megabytes * 1024.0 * 1024.0) as u64)
+        }
+        Some(_) => return Err("目标大小必须大于 0".into()),
+        None => None,
+    };
     CONVERT_CANCELLED.store(false, Ordering::SeqCst);
 
-    let ffmpeg = get_ffmpeg_path(&app);
-    let duration = get_duration(&app, &input)?;
-
-    info!(
-        "[转换] {} -> {} ({}, 画质: {})",
-        input, output, format, quality
-    );
-
     let output_clone = output.clone();
     let output_for_cleanup = output.clone();
     let cancelled = CONVERT_CANCELLED.clone();
     let format = format.clone();
 
-    // 根据画质选择 CRF 值（越小质量越高）
-    let crf = match quality.as_str() {
-        "high" => "18",
-        "medium" => "23",
-        "low" => "28",
-        _ => "20",
-    };
-
     let result = tokio::task::spawn_blocking(move || {
+        let ffmpeg = get_ffmpeg_path(&app);
+        let duration = get_duration(&app, &input)?;
+        let encoder = if format == "gif" {
+            "gif"
+        } else {
+            select_encoder(codec, &format, &available_encoders(&ffmpeg)?)?
+        };
+        let video_filters = if transform == VideoTransform::default() {
+            Vec::new()
+        } else {
+            let info = get_video_info(app.clone(), input.clone())?;
+            // ffmpeg 会先按旋转信息摆正画面，再进入滤镜
+            let (width, height) = if info.rotation.rem_euclid(180) == 90 {
+                (info.height, info.width)
+            } else {
+                (info.width, info.height)
+            };
+            transform.build_filters(width, height)?
+        };
+        let video_filter = (!video_filters.is_empty()).then(|| video_filters.join(","));
+
+        info!(
+            "[转换] {} -> {} ({}, {}, 画质: {})",
+            input, output_clone, format, encoder, quality
+        );
+
+        // GIF 没有音频，不需要标准化
+        let normalization = match normalize_loudness.filter(|_| format != "gif") {
+            Some(target) => Some(prepare_normalization(
+                &app,
+                &input,
+                &target,
+                TimeRange {
+                    start: 0.0,
+                    end: duration,
+                },
+                &cancelled,
+                &CONVERT_PROCESS,
+                |progress| {
+                    let _ = app.emit("convert-progress", progress * MEASURE_PHASE_WEIGHT / 100.0);
+                },
+            )?),
+            None => None,
+        };
+        let offset = if normalization.is_some() {
+            MEASURE_PHASE_WEIGHT
+        } else {
+            0.0
+        };
+
+        if let Some(target_bytes) = target_bytes {
+            let report = run_target_size_encode(
+                &ffmpeg,
+                TargetSizeJob {
+                    input: &input,
+                    output: &output_clone,
+                    duration,
+                    target_bytes,
+                    video_filter: video_filter.as_deref(),
+                    audio_filter: normalization.as_ref().map(|(_, filter)| filter.as_str()),
+                },
+                &cancelled,
+                |progress| {
+                    let _ = app.emit(
+                        "convert-progress",
+                        offset + progress * (100.0 - offset) / 100.0,
+                    );
+                },
+            )?;
+            info!(
+                "[转换] 目标大小 {:.2} MB，实际 {:.2} MB ({:+.1}%)",
+                report.target_bytes as f64 / 1024.0 / 1024.0,
+                report.actual_bytes as f64 / 1024.0 / 1024.0,
+                (report.actual_bytes as f64 / report.target_bytes as f64 - 1.0) * 100.0
+            );
+            let _ = app.emit("convert-size-report", report);
+            return Ok(true);
+        }
+
         let mut args = vec![
             "-y".to_string(),
             "-i".to_string(),
@@ -76,30 +761,39 @@ pub async fn convert_video(
         ];
 
         if format == "gif" {
-            // GIF：限制帧率和尺寸
+            // GIF：未指定时限制帧率和尺寸
+            let mut gif_filters = video_filters;
+            if transform.fps.is_none() {
+                gif_filters.push("fps=12".to_string());
+            }
+            if transform.width.is_none() && transform.height.is_none() {
+                gif_filters.push("scale='min(480,iw)':-1:flags=lanczos".to_string());
+            }
             args.extend([
                 "-vf".to_string(),
-                "fps=12,scale='min(480,iw)':-1:flags=lanczos".to_string(),
+                gif_filters.join(","),
                 "-c:v".to_string(),
                 "gif".to_string(),
                 "-an".to_string(),
             ]);
         } else {
-            // MP4/MOV：使用 libx264 软编码（兼容性最好）
-            args.extend([
-                "-c:v".to_string(),
-                "libx264".to_string(),
-                "-crf".to_string(),
-                crf.to_string(),
-                "-preset".to_string(),
-                "fast".to_string(),
-                "-pix_fmt".to_string(),
-                "yuv420p".to_string(),
-                "-c:a".to_string(),
-                "aac".to_string(),
-                "-b:a".to_string(),
-                "192k".to_string(),
-            ]);
+            if let Some(filter) = &video_filter {
+                args.push("-vf".to_string());
+                args.push(filter.clone());
+            }
+            args.extend(video_encoder_args(encoder, &quality, &format));
+            args.extend(codec.audio_args(&format).map(str::to_string));
+            if let Some((_, filter)) = &normalization {
+                // 响度只测量了第一条音轨
+                args.extend([
+                    "-map".to_string(),
+                    "0:v:0".to_string(),
+                    "-map".to_string(),
+                    "0:a:0".to_string(),
+                    "-af".to_string(),
+                    filter.clone(),
+                ]);
+            }
         }
 
         args.extend(["-progress".to_string(), "pipe:1".to_string()]);
@@ -128,7 +822,10 @@ pub async fn convert_video(
             if let Some(time_str) = line.strip_prefix("out_time=") {
                 if let Some(secs) = parse_ffmpeg_time(time_str) {
                     let progress = (secs / duration * 100.0).min(100.0).max(0.0);
-                    let _ = app.emit("convert-progress", progress);
+                    let _ = app.emit(
+                        "convert-progress",
+                        offset + progress * (100.0 - offset) / 100.0,
+                    );
                 }
             }
         }
@@ -154,6 +851,116 @@ pub async fn convert_video(
     }
 }
 
+/// 提取音轨并导出为音频文件，源编码与目标格式匹配时直接复制
+///
+/// `track` 为第几条音轨（从 0 开始），默认第一条。进度通过 `convert-progress` 事件上报，
+/// 使用 `cancel_convert` 取消。
+#[tauri::command]
+pub async fn extract_audio(
+    app: AppHandle,
+    input: String,
+    output: String,
+    format: AudioFormat,
+    bitrate_kbps: Option<u32>,
+    sample_rate: Option<u32>,
+    track: Option<usize>,
+) -> Result<AudioExtractResult, String> {
+    CONVERT_CANCELLED.store(false, Ordering::SeqCst);
+    let cancelled = CONVERT_CANCELLED.clone();
+    let track = track.unwrap_or(0);
+
+    let output_for_task = output.clone();
+    let stream_copy = tokio::task::spawn_blocking(move || {
+        let info = probe_media_info(&app, &input)?;
+        let audio_streams: Vec<_> = info
+            .streams
+            .iter()
+            .filter(|stream| stream.kind == StreamKind::Audio)
+            .collect();
+        if audio_streams.is_empty() {
+            return Err("文件中没有音轨".to_string());
+        }
+        let stream = audio_streams.get(track).ok_or_else(|| {
+            format!(
+                "音轨 {} 不存在，文件共有 {} 条音轨",
+                track + 1,
+                audio_streams.len()
+            )
+        })?;
+
+        let (codec_args, stream_copy) = build_audio_codec_args(
+            format,
+            stream.codec_name.as_deref(),
+            stream.audio.as_ref().and_then(|audio| audio.sample_rate),
+            bitrate_kbps,
+            sample_rate,
+        )?;
+        info!(
+            "[音频] {} -> {} ({:?}, 音轨 {}, {})",
+            input,
+            output_for_task,
+            format,
+            track + 1,
+            if stream_copy {
+                "直接复制"
+            } else {
+                "重新编码"
+            }
+        );
+
+        let mut args = vec![
+            "-y".to_string(),
+            "-i".to_string(),
+            input.clone(),
+            "-map".to_string(),
+            format!("0:a:{}", track),
+            "-vn".to_string(),
+            "-sn".to_string(),
+            "-dn".to_string(),
+            "-map_metadata".to_string(),
+            "0".to_string(),
+        ];
+        args.extend(codec_args);
+        if format == AudioFormat::M4a {
+            args.push("-movflags".to_string());
+            args.push("+faststart".to_string());
+        }
+
+        let duration = info
+            .container
+            .duration
+            .or(stream.duration)
+            .unwrap_or_default();
+        run_ffmpeg_with_progress(
+            &get_ffmpeg_path(&app),
+            &args,
+            &output_for_task,
+            duration,
+            &cancelled,
+            &CONVERT_PROCESS,
+            |progress| {
+                let _ = app.emit("convert-progress", progress);
+            },
+        )
+        .map_err(|error| {
+            if cancelled.load(Ordering::SeqCst) {
+                error
+            } else {
+                format!("音频导出失败: {}", error)
+            }
+        })?;
+        Ok(stream_copy)
+    })
+    .await
+    .map_err(|e| format!("任务执行失败: {}", e))??;
+
+    info!("[音频] 完成: {}", output);
+    Ok(AudioExtractResult {
+        output_path: output,
+        stream_copy,
+    })
+}
+
 /// 取消转换
 #[tauri::command]
 pub fn cancel_convert() {
@@ -174,18 +981,6 @@ pub fn cancel_convert() {
     }
 }
 
-fn parse_ffmpeg_time(time_str: &str) -> Option<f64> {
-    let parts: Vec<&str> = time_str.split(':').collect();
-    if parts.len() == 3 {
-        let hours: f64 = parts[0].parse().ok()?;
-        let minutes: f64 = parts[1].parse().ok()?;
-        let seconds: f64 = parts[2].parse().ok()?;
-        Some(hours * 3600.0 + minutes * 60.0 + seconds)
-    } else {
-        None
-    }
-}
-
 /// 获取文件大小
 #[tauri::command]
 pub fn get_file_size(path: String) -> Result<u64, String> {
@@ -193,3 +988,119 @@ pub fn get_file_size(path: String) -> Result<u64, String> {
         .map(|m| m.len())
         .map_err(|e| format!("获取文件大小失败: {}", e))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn video_transform_builds_single_filter_chain() {
+        let transform = VideoTransform {
+            crop: Some(CropRect {
+                x: 0,
+                y: 420,
+                width: 2160,
+                height: 3000,
+            }),
+            rotate: Some(Rotation::Clockwise),
+            width: Some(1920),
+            height: Some(1080),
+            scale_mode: ScaleMode::Pad,
+            fps: Some(30.0),
+            ..VideoTransform::default()
+        };
+        assert_eq!(
+            transform.build_filters(2160, 3840).unwrap().join(","),
+            "crop=2160:3000:0:420,transpose=clock,\
+             scale=1920:1080:force_original_aspect_ratio=decrease:force_divisible_by=2,\
+             pad=1920:1080:(ow-iw)/2:(oh-ih)/2,setsar=1,fps=30"
+        );
+
+        assert!(transform.build_filters(1920, 1080).is_err());
+        let overflow = VideoTransform {
+            crop: Some(CropRect {
+                x: u32::MAX - 10,
+                y: 0,
+                width: 100,
+                height: 100,
+            }),
+            ..VideoTransform::default()
+        };
+        assert!(overflow.build_filters(1920, 1080).is_err());
+        assert!(transform.build_filters(0, 0).is_err());
+        let odd = VideoTransform {
+            width: Some(1279),
+            ..VideoTransform::default()
+        };
+        assert!(odd.build_filters(1920, 1080).is_err());
+        assert!(VideoTransform::default()
+            .build_filters(1920, 1080)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn select_encoder_checks_bundled_ffmpeg() {
+        let listing = "Encoders:\n V..... = Video\n ------\n V....D libx264              libx264 H.264\n V....D libaom-av1           libaom AV1\n A....D aac                  AAC\n A....D libopus              libopus Opus\n";
+        let available = parse_encoder_list(listing);
+        assert!(available.contains("libaom-av1"));
+        assert!(!available.contains("="));
+
+        assert_eq!(
+            select_encoder(VideoCodec::Av1, "webm", &available),
+            Ok("libaom-av1")
+        );
+        assert!(select_encoder(VideoCodec::Hevc, "mp4", &available).is_err());
+        assert!(!VideoCodec::H264.supports_container("webm"));
+        assert_eq!(VideoCodec::Vp9.audio_args("mkv")[1], "libopus");
+        assert_eq!(VideoCodec::Hevc.audio_args("mkv")[1], "aac");
+    }
+
+    #[test]
+    fn target_video_bitrate_leaves_room_for_audio() {
+        // 25 MB、60 秒：约 3390 kbps 总码率，扣掉 128k 音频
+        let kbps = target_video_bitrate_kbps(25 * 1024 * 1024, 60.0, 128).unwrap();
+        assert_eq!(kbps, 3262);
+        assert!(target_video_bitrate_kbps(1024 * 1024, 600.0, 128).is_err());
+        assert!(target_video_bitrate_kbps(1024 * 1024, 0.0, 128).is_err());
+    }
+
+    #[test]
+    fn build_audio_codec_args_copies_only_when_nothing_changes() {
+        let (args, copy) =
+            build_audio_codec_args(AudioFormat::M4a, Some("aac"), Some(48_000), None, None)
+                .unwrap();
+        assert!(copy);
+        assert_eq!(args, vec!["-c:a", "copy"]);
+
+        let (_, copy) =
+            build_audio_codec_args(AudioFormat::Wav, Some("pcm_s24le"), None, None, None).unwrap();
+        assert!(copy);
+        let (args, copy) =
+            build_audio_codec_args(AudioFormat::Wav, Some("pcm_s16be"), None, None, None).unwrap();
+        assert!(!copy);
+        assert_eq!(args, vec!["-c:a", "pcm_s16le"]);
+
+        let (args, copy) =
+            build_audio_codec_args(AudioFormat::Mp3, Some("mp3"), Some(44_100), Some(128), None)
+                .unwrap();
+        assert!(!copy);
+        assert_eq!(args, vec!["-c:a", "libmp3lame", "-b:a", "128k"]);
+
+        let (args, copy) = build_audio_codec_args(
+            AudioFormat::Flac,
+            Some("aac"),
+            Some(44_100),
+            Some(320),
+            Some(48_000),
+        )
+        .unwrap();
+        assert!(!copy);
+        assert_eq!(args, vec!["-c:a", "flac", "-ar", "48000"]);
+
+        assert!(
+            build_audio_codec_args(AudioFormat::Opus, Some("aac"), None, None, Some(44_100))
+                .is_err()
+        );
+    }
+}
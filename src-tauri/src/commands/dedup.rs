@@ -638,6 +638,15 @@ pub async fn find_duplicates(
     Ok(result)
 }
 
+/// 删除单个文件：移到回收站，或直接永久删除
+pub(crate) fn remove_file_path(path: &Path, use_trash: bool) -> Result<(), String> {
+    if use_trash {
+        trash::delete(path).map_err(|error| error.to_string())
+    } else {
+        fs::remove_file(path).map_err(|error| error.to_string())
+    }
+}
+
 #[tauri::command]
 pub fn delete_files(
     paths: Vec<String>,
@@ -659,15 +668,7 @@ pub fn delete_files(
     let mut deleted_count = 0u32;
 
     for path in verified_paths {
-        let delete_result = if use_trash {
-            trash::delete(&path)
-        } else {
-            fs::remove_file(&path).map_err(|error| trash::Error::Unknown {
-                description: error.to_string(),
-            })
-        };
-
-        match delete_result {
+        match remove_file_path(Path::new(&path), use_trash) {
             Ok(()) => {
                 deleted_count += 1;
                 debug!("[删除] 已删除: {}", path);
@@ -676,7 +677,7 @@ pub fn delete_files(
                 warn!("[删除] 删除失败: {} ({})", path, error);
                 failed.push(DeleteFailure {
                     path,
-                    reason: error.to_string(),
+                    reason: error,
                 });
             }
         }
@@ -1,6 +1,14 @@
-use std::path::PathBuf;
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
 use tauri::{AppHandle, Manager};
 
+/// ffmpeg 失败时保留的 stderr 末尾行数
+const STDERR_TAIL_LINES: usize = 8;
+
 /// 获取内嵌的 ffmpeg 路径
 pub fn get_ffmpeg_path(app: &AppHandle) -> PathBuf {
     // Tauri 2.0 externalBin 会把文件放到 MacOS 目录（macOS）或 exe 同目录（Windows）
@@ -95,3 +103,270 @@ fn get_target() -> &'static str {
         "x86_64-unknown-linux-gnu"
     }
 }
+
+/// 解析 ffmpeg 输出的 `HH:MM:SS.xx` 时间
+pub(crate) fn parse_ffmpeg_time(time_str: &str) -> Option<f64> {
+    let parts: Vec<&str> = time_str.split(':').collect();
+    if parts.len() == 3 {
+        let hours: f64 = parts[0].parse().ok()?;
+        let minutes: f64 = parts[1].parse().ok()?;
+        let seconds: f64 = parts[2].parse().ok()?;
+        Some(hours * 3600.0 + minutes * 60.0 + seconds)
+    } else {
+        None
+    }
+}
+
+/// 解析 `-progress pipe:1` 输出中的已处理时长（秒）
+pub fn parse_progress_seconds(line: &str) -> Option<f64> {
+    if let Some(value) = line
+        .strip_prefix("out_time_us=")
+        .or_else(|| line.strip_prefix("out_time_ms="))
+    {
+        // out_time_ms 实际上也是微秒
+        value.parse::<i64>().ok().map(|us| us as f64 / 1_000_000.0)
+    } else {
+        line.strip_prefix("out_time=").and_then(parse_ffmpeg_time)
+    }
+}
+
+/// 运行 ffmpeg 并按输出时长回调进度（0-100），输出文件放在最后一个参数
+///
+/// 进程 ID 记录在 `process` 中，取消命令可以直接结束进程；被取消或失败时删除输出文件。
+/// 失败时返回 stderr 末尾几行，便于定位问题。
+pub fn run_ffmpeg_with_progress<F>(
+    ffmpeg: &Path,
+    args: &[String],
+    output: &str,
+    duration: f64,
+    cancelled: &AtomicBool,
+    process: &Mutex<Option<u32>>,
+    on_progress: F,
+) -> Result<(), String>
+where
+    F: FnMut(f64),
+{
+    run_ffmpeg(
+        ffmpeg,
+        args,
+        Some(output),
+        duration,
+        cancelled,
+        process,
+        on_progress,
+    )
+    .map(|_| ())
+}
+
+/// 运行只做分析的 ffmpeg（输出到 null），返回完整的 stderr 供解析滤镜日志
+///
+/// `args` 中应包含 `-nostats`，否则统计行会混在日志里。
+pub fn run_ffmpeg_analysis<F>(
+    ffmpeg: &Path,
+    args: &[String],
+    duration: f64,
+    cancelled: &AtomicBool,
+    process: &Mutex<Option<u32>>,
+    on_progress: F,
+) -> Result<String, String>
+where
+    F: FnMut(f64),
+{
+    run_ffmpeg(
+        ffmpeg,
+        args,
+        None,
+        duration,
+        cancelled,
+        process,
+        on_progress,
+    )
+}
+
+/// `output` 为空时输出到 null 并保留完整 stderr，否则只保留末尾几行
+fn run_ffmpeg<F>(
+    ffmpeg: &Path,
+    args: &[String],
+    output: Option<&str>,
+    duration: f64,
+    cancelled: &AtomicBool,
+    process: &Mutex<Option<u32>>,
+    mut on_progress: F,
+) -> Result<String, String>
+where
+    F: FnMut(f64),
+{
+    let mut command = Command::new(ffmpeg);
+    command.args(args).args(["-progress", "pipe:1"]);
+    match output {
+        Some(output) => command.arg(output),
+        None => command.args(["-f", "null", "-"]),
+    };
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("启动 ffmpeg 失败: {}", e))?;
+    *process.lock().unwrap_or_else(|p| p.into_inner()) = Some(child.id());
+
+    // stderr 必须持续读取，否则缓冲区写满后 ffmpeg 会阻塞
+    let keep_all = output.is_none();
+    let stderr_reader = child.stderr.take().map(|stderr| {
+        std::thread::spawn(move || {
+            let mut lines = VecDeque::with_capacity(STDERR_TAIL_LINES);
+            for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                if !keep_all && lines.len() == STDERR_TAIL_LINES {
+                    lines.pop_front();
+                }
+                lines.push_back(line);
+            }
+            lines
+        })
+    });
+
+    if let Some(stdout) = child.stdout.take() {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if cancelled.load(Ordering::SeqCst) {
+                let _ = child.kill();
+                break;
+            }
+            if let Some(seconds) = parse_progress_seconds(&line) {
+                if duration > 0.0 {
+                    on_progress((seconds / duration * 100.0).clamp(0.0, 100.0));
+                }
+            }
+        }
+    }
+
+    let status = child.wait();
+    *process.lock().unwrap_or_else(|p| p.into_inner()) = None;
+    let stderr_lines = stderr_reader
+        .and_then(|handle| handle.join().ok())
+        .unwrap_or_default();
+    let remove_output = || {
+        if let Some(output) = output {
+            let _ = std::fs::remove_file(output);
+        }
+    };
+
+    if cancelled.load(Ordering::SeqCst) {
+        remove_output();
+        return Err("操作已取消".to_string());
+    }
+    let status = status.map_err(|e| format!("等待 ffmpeg 失败: {}", e))?;
+    if !status.success() {
+        remove_output();
+        let skip = stderr_lines.len().saturating_sub(STDERR_TAIL_LINES);
+        let stderr_tail = Vec::from(stderr_lines).split_off(skip).join("\n");
+        return Err(if stderr_tail.trim().is_empty() {
+            format!("ffmpeg 异常退出 ({})", status)
+        } else {
+            stderr_tail.trim().to_string()
+        });
+    }
+
+    on_progress(100.0);
+    Ok(Vec::from(stderr_lines).join("\n"))
+}
+
+/// concat 列表中的路径用单引号包裹，路径里的单引号需要转义
+fn escape_concat_path(path: &Path) -> String {
+    format!("'{}'", path.to_string_lossy().replace('\'', "'\\''"))
+}
+
+/// 写入 concat demuxer 使用的文件列表（需配合 `-f concat -safe 0`）
+pub fn write_concat_list(list_path: &Path, files: &[PathBuf]) -> Result<(), String> {
+    let content: String = files
+        .iter()
+        .map(|file| format!("file {}\n", escape_concat_path(file)))
+        .collect();
+    std::fs::write(list_path, content).map_err(|error| format!("写入拼接列表失败: {}", error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_progress_seconds_reads_all_time_fields() {
+        assert_eq!(parse_progress_seconds("out_time_us=2500000"), Some(2.5));
+        assert_eq!(parse_progress_seconds("out_time_ms=1000000"), Some(1.0));
+        assert_eq!(
+            parse_progress_seconds("out_time=00:01:02.500000"),
+            Some(62.5)
+        );
+        assert_eq!(parse_progress_seconds("out_time=N/A"), None);
+        assert_eq!(parse_progress_seconds("frame=120"), None);
+    }
+
+    // 用 sh 模拟 ffmpeg：脚本之后追加的 -progress 等参数会成为位置参数被忽略
+    #[cfg(unix)]
+    #[test]
+    fn run_ffmpeg_analysis_returns_full_stderr() {
+        let log = run_ffmpeg_analysis(
+            Path::new("sh"),
+            &[
+                "-c".to_string(),
+                "for i in 1 2 3 4 5 6 7 8 9 10; do echo line $i >&2; done".to_string(),
+            ],
+            1.0,
+            &AtomicBool::new(false),
+            &Mutex::new(None),
+            |_| {},
+        )
+        .expect("script should succeed");
+        assert_eq!(log.lines().count(), 10);
+        assert!(log.starts_with("line 1\n"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn run_ffmpeg_with_progress_reports_progress_and_stderr_tail() {
+        let process = Mutex::new(None);
+        let cancelled = AtomicBool::new(false);
+        let output = std::env::temp_dir().join(format!("ffmpeg-utils-test-{}", std::process::id()));
+        let output = output.to_string_lossy().to_string();
+
+        let mut progress = Vec::new();
+        run_ffmpeg_with_progress(
+            Path::new("sh"),
+            &[
+                "-c".to_string(),
+                "echo out_time_us=500000; echo out_time=00:00:01.000000".to_string(),
+            ],
+            &output,
+            2.0,
+            &cancelled,
+            &process,
+            |value| progress.push(value),
+        )
+        .expect("script should succeed");
+        assert_eq!(progress, vec![25.0, 50.0, 100.0]);
+        assert!(process.lock().unwrap().is_none());
+
+        std::fs::write(&output, b"partial").expect("failed to write partial output");
+        let error = run_ffmpeg_with_progress(
+            Path::new("sh"),
+            &[
+                "-c".to_string(),
+                "echo 'Invalid data found' >&2; exit 1".to_string(),
+            ],
+            &output,
+            2.0,
+            &cancelled,
+            &process,
+            |_| {},
+        )
+        .expect_err("script should fail");
+        assert_eq!(error, "Invalid data found");
+        assert!(!Path::new(&output).exists());
+    }
+
+    #[test]
+    fn escape_concat_path_quotes_single_quotes() {
+        assert_eq!(
+            escape_concat_path(Path::new("/tmp/it's.mp4")),
+            "'/tmp/it'\\''s.mp4'"
+        );
+    }
+}
@@ -1,19 +1,35 @@
+use glob::Pattern;
 use lazy_static::lazy_static;
-use serde::Serialize;
-use std::collections::HashMap;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
 use std::fs::Metadata;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use tauri::{AppHandle, Emitter};
-use walkdir::WalkDir;
+
+use super::file_stats_compression::{CompressionCandidates, CompressionEstimate};
+use super::file_stats_health::HealthReport;
+use super::file_stats_ownership::{OwnershipReport, OwnershipTotals};
+use super::file_stats_versions::{version_file, version_key, VersionFile, VersionReport};
 
 lazy_static! {
     static ref FILE_STATS_CANCELLED: Mutex<HashMap<String, Arc<AtomicBool>>> =
         Mutex::new(HashMap::new());
 }
 
+// 正在进行的用户扫描数量；取消表里可能残留已结束任务的键，不能用来判断
+static FOREGROUND_SCANS: AtomicUsize = AtomicUsize::new(0);
+
 const MAX_ERROR_SAMPLES: usize = 3;
+const MAX_SPARSE_SAMPLES: usize = 50;
+// 估算压缩率时最多抽样的类型数，每种类型最多读取约 1 MiB
+const MAX_COMPRESSION_TYPES: usize = 32;
+// 小于该大小的文件可能被文件系统内联存储，分配大小为 0 并不代表稀疏
+const SPARSE_MIN_SIZE: u64 = 1024 * 1024;
 
 fn lock_cancelled_tasks() -> std::sync::MutexGuard<'static, HashMap<String, Arc<AtomicBool>>> {
     FILE_STATS_CANCELLED
@@ -43,11 +59,124 @@ fn mark_task_cancelled(task_id: &str) {
     cancelled.store(true, Ordering::Relaxed);
 }
 
+/// 后台定时扫描使用的任务 ID 前缀，用户发起的扫描会抢占这些任务
+const BACKGROUND_TASK_PREFIX: &str = "background-";
+
+fn is_background_task(task_id: &str) -> bool {
+    task_id.starts_with(BACKGROUND_TASK_PREFIX)
+}
+
+fn preempt_background_tasks() {
+    let tasks = lock_cancelled_tasks();
+    for (task_id, cancelled) in tasks.iter() {
+        if is_background_task(task_id) {
+            cancelled.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+/// 是否有用户发起的扫描正在进行
+pub(crate) fn has_foreground_scan() -> bool {
+    FOREGROUND_SCANS.load(Ordering::SeqCst) > 0
+}
+
+/// 用户扫描期间持有，结束（包括出错和 panic）时自动减计数
+struct ForegroundScanGuard;
+
+impl ForegroundScanGuard {
+    fn enter() -> Self {
+        FOREGROUND_SCANS.fetch_add(1, Ordering::SeqCst);
+        Self
+    }
+}
+
+impl Drop for ForegroundScanGuard {
+    fn drop(&mut self) {
+        FOREGROUND_SCANS.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// 后台扫描的错误，被用户扫描抢占时为 `Cancelled`
+#[derive(Debug, PartialEq)]
+pub(crate) enum BackgroundScanError {
+    Cancelled,
+    Failed(String),
+}
+
 fn get_file_size(metadata: &Metadata) -> u64 {
     metadata.len()
 }
 
-fn normalize_extension(path: &std::path::Path) -> String {
+/// 实际占用的磁盘空间（与 du 一致，Unix 上为 st_blocks * 512）
+#[cfg(unix)]
+fn get_allocated_size(metadata: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.blocks().saturating_mul(512)
+}
+
+#[cfg(not(unix))]
+fn get_allocated_size(metadata: &Metadata) -> u64 {
+    metadata.len()
+}
+
+/// 多个硬链接指向同一 inode 时只统计一次占用空间，返回 None 表示无需去重
+#[cfg(unix)]
+fn hard_link_key(metadata: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    if metadata.is_dir() || metadata.nlink() <= 1 {
+        return None;
+    }
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn hard_link_key(_metadata: &Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+fn is_sparse(apparent_size: u64, allocated_size: u64) -> bool {
+    apparent_size >= SPARSE_MIN_SIZE && allocated_size < apparent_size / 2
+}
+
+#[cfg(unix)]
+fn device_id(metadata: &Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.dev())
+}
+
+#[cfg(not(unix))]
+fn device_id(_metadata: &Metadata) -> Option<u64> {
+    None
+}
+
+fn has_hidden_name(name: &OsStr) -> bool {
+    name.to_string_lossy().starts_with('.')
+}
+
+#[cfg(windows)]
+fn has_hidden_attribute(metadata: &Metadata) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+    metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0
+}
+
+#[cfg(not(windows))]
+fn has_hidden_attribute(_metadata: &Metadata) -> bool {
+    false
+}
+
+fn compile_exclude_patterns(globs: &[String]) -> Result<Vec<Pattern>, String> {
+    globs
+        .iter()
+        .map(|value| value.trim())
+        .filter(|value| !value.is_empty())
+        .map(|value| {
+            Pattern::new(value).map_err(|error| format!("无效的排除规则 {}: {}", value, error))
+        })
+        .collect()
+}
+
+fn normalize_extension(path: &Path) -> String {
     path.extension()
         .map(|ext| {
             let ext = ext.to_string_lossy();
@@ -62,7 +191,7 @@ fn normalize_extension(path: &std::path::Path) -> String {
 
 fn push_scan_issue(
     sample_errors: &mut Vec<ScanIssue>,
-    path: Option<&std::path::Path>,
+    path: Option<&Path>,
     reason: impl Into<String>,
 ) {
     if sample_errors.len() >= MAX_ERROR_SAMPLES {
@@ -77,8 +206,162 @@ fn push_scan_issue(
     });
 }
 
+fn is_permission_denied(error: &std::io::Error) -> bool {
+    error.kind() == std::io::ErrorKind::PermissionDenied
+}
+
+/// 多线程扫描共享的状态：取消标记、进度计数和硬链接去重
+struct ScanContext<'a, F> {
+    task_id: &'a str,
+    root: &'a Path,
+    options: &'a ScanOptions,
+    exclude_patterns: Vec<Pattern>,
+    root_device: Option<u64>,
+    cancelled: &'a AtomicBool,
+    emit: &'a F,
+    scan_start: Instant,
+    last_progress_emit: Mutex<Option<Instant>>,
+    scanned_files: AtomicU64,
+    skipped_files: AtomicU64,
+    permission_denied_files: AtomicU64,
+    seen_hard_links: Mutex<HashSet<(u64, u64)>>,
+}
+
+impl<F> ScanContext<'_, F>
+where
+    F: Fn(FileStatsProgress) + Sync,
+{
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    fn record_skipped(
+        &self,
+        partial: &mut ScanPartial,
+        path: Option<&Path>,
+        error: &std::io::Error,
+        reason: String,
+    ) {
+        self.skipped_files.fetch_add(1, Ordering::Relaxed);
+        if is_permission_denied(error) {
+            self.permission_denied_files.fetch_add(1, Ordering::Relaxed);
+        }
+        push_scan_issue(&mut partial.sample_errors, path, reason);
+    }
+
+    /// 首个文件立即上报，之后每 200ms 最多上报一次；拿不到锁说明其他线程正在上报
+    fn maybe_emit_progress(&self) {
+        let Ok(mut last_emit) = self.last_progress_emit.try_lock() else {
+            return;
+        };
+        if last_emit.is_some_and(|instant| instant.elapsed() < Duration::from_millis(200)) {
+            return;
+        }
+        *last_emit = Some(Instant::now());
+
+        emit_progress(
+            self.emit,
+            self.task_id,
+            "扫描文件".into(),
+            self.scanned_files.load(Ordering::Relaxed),
+            0,
+            0.0,
+            self.scan_start.elapsed(),
+            self.skipped_files.load(Ordering::Relaxed),
+            self.permission_denied_files.load(Ordering::Relaxed),
+        );
+    }
+
+    /// 排除规则同时匹配文件名和相对扫描根目录的路径（统一使用 / 分隔）
+    fn is_excluded_by_glob(&self, path: &Path, name: &OsStr) -> bool {
+        if self.exclude_patterns.is_empty() {
+            return false;
+        }
+
+        let name = name.to_string_lossy();
+        let relative = path
+            .strip_prefix(self.root)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .replace('\\', "/");
+        self.exclude_patterns
+            .iter()
+            .any(|pattern| pattern.matches(&name) || pattern.matches(&relative))
+    }
+
+    /// 硬链接只在第一次遇到时计入占用空间
+    fn claim_allocation(&self, metadata: &Metadata) -> u64 {
+        match hard_link_key(metadata) {
+            Some(key) => {
+                let mut seen = self
+                    .seen_hard_links
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+                if seen.insert(key) {
+                    get_allocated_size(metadata)
+                } else {
+                    0
+                }
+            }
+            None => get_allocated_size(metadata),
+        }
+    }
+}
+
+/// 单个子树的统计结果，并行扫描结束后逐级合并
+#[derive(Debug, Default)]
+struct ScanPartial {
+    stats: HashMap<String, ExtensionTotals>,
+    total_files: u64,
+    folder_count: u64,
+    total_size: u64,
+    total_allocated_size: u64,
+    sparse_files: u64,
+    sparse_samples: Vec<SparseFile>,
+    excluded: ScanExclusions,
+    ownership: OwnershipTotals,
+    health: HealthReport,
+    versions: VersionReport,
+    sample_errors: Vec<ScanIssue>,
+}
+
+impl ScanPartial {
+    fn merge(mut self, other: ScanPartial) -> ScanPartial {
+        for (ext, totals) in other.stats {
+            let stat = self.stats.entry(ext).or_default();
+            stat.count += totals.count;
+            stat.size += totals.size;
+            stat.allocated_size += totals.allocated_size;
+            stat.sparse_count += totals.sparse_count;
+            stat.compression_candidates
+                .merge(totals.compression_candidates);
+        }
+        self.total_files += other.total_files;
+        self.folder_count += other.folder_count;
+        self.total_size += other.total_size;
+        self.total_allocated_size += other.total_allocated_size;
+        self.sparse_files += other.sparse_files;
+        self.excluded.by_glob += other.excluded.by_glob;
+        self.excluded.hidden += other.excluded.hidden;
+        self.excluded.by_depth += other.excluded.by_depth;
+        self.excluded.other_filesystem += other.excluded.other_filesystem;
+        self.excluded.symlink_loops += other.excluded.symlink_loops;
+        self.ownership.merge(other.ownership);
+        self.health.merge(other.health);
+        self.versions.merge(other.versions);
+
+        let sparse_room = MAX_SPARSE_SAMPLES.saturating_sub(self.sparse_samples.len());
+        self.sparse_samples
+            .extend(other.sparse_samples.into_iter().take(sparse_room));
+        let error_room = MAX_ERROR_SAMPLES.saturating_sub(self.sample_errors.len());
+        self.sample_errors
+            .extend(other.sample_errors.into_iter().take(error_room));
+        self
+    }
+}
+
 fn emit_progress<F>(
-    emit: &mut F,
+    emit: &F,
     task_id: &str,
     stage: String,
     current: u64,
@@ -88,7 +371,7 @@ fn emit_progress<F>(
     skipped_files: u64,
     permission_denied_files: u64,
 ) where
-    F: FnMut(FileStatsProgress),
+    F: Fn(FileStatsProgress),
 {
     let elapsed_ms = elapsed.as_millis().min(u64::MAX as u128) as u64;
     let processed = current.saturating_add(skipped_files);
@@ -111,144 +394,337 @@ fn emit_progress<F>(
     });
 }
 
-fn scan_directory_inner<F>(
-    path: &str,
-    task_id: &str,
-    cancelled: &AtomicBool,
-    mut emit: F,
-) -> Result<ScanResult, String>
-where
-    F: FnMut(FileStatsProgress),
+fn scan_file<F>(
+    ctx: &ScanContext<'_, F>,
+    partial: &mut ScanPartial,
+    path: &Path,
+    metadata: &Metadata,
+) where
+    F: Fn(FileStatsProgress) + Sync,
 {
-    let root_metadata =
-        std::fs::metadata(path).map_err(|error| format!("无法访问所选文件夹: {}", error))?;
-    if !root_metadata.is_dir() {
-        return Err("请选择文件夹，而不是单个文件".into());
+    let ext = normalize_extension(path);
+    let size = get_file_size(metadata);
+    let file_allocated_size = get_allocated_size(metadata);
+    let allocated_size = ctx.claim_allocation(metadata);
+
+    let stat = partial.stats.entry(ext).or_default();
+    stat.count += 1;
+    stat.size += size;
+    stat.allocated_size += allocated_size;
+    if ctx.options.estimate_compression {
+        stat.compression_candidates.offer(path, size);
     }
 
-    let mut stats: HashMap<String, (u64, u64)> = HashMap::new();
-    let mut total_files: u64 = 0;
-    let mut folder_count: u64 = 0;
-    let mut total_size: u64 = 0;
-    let mut skipped_files: u64 = 0;
-    let mut permission_denied_files: u64 = 0;
-    let mut sample_errors = Vec::new();
-    let scan_start = Instant::now();
-    let mut last_progress_emit = Instant::now();
+    if is_sparse(size, file_allocated_size) {
+        partial.sparse_files += 1;
+        stat.sparse_count += 1;
+        if partial.sparse_samples.len() < MAX_SPARSE_SAMPLES {
+            partial.sparse_samples.push(SparseFile {
+                path: path.display().to_string(),
+                size,
+                allocated_size: file_allocated_size,
+            });
+        }
+    }
 
-    emit_progress(
-        &mut emit,
-        task_id,
-        "扫描文件".into(),
-        0,
-        0,
-        0.0,
-        scan_start.elapsed(),
-        skipped_files,
-        permission_denied_files,
-    );
+    if ctx.options.include_ownership {
+        partial.ownership.record(path, metadata, allocated_size);
+    }
 
-    for entry_result in WalkDir::new(path).into_iter() {
-        if cancelled.load(Ordering::Relaxed) {
-            return Err("操作已取消".to_string());
+    partial.total_files += 1;
+    partial.total_size += size;
+    partial.total_allocated_size += allocated_size;
+
+    ctx.scanned_files.fetch_add(1, Ordering::Relaxed);
+    ctx.maybe_emit_progress();
+}
+
+/// 扫描一个目录：当前层的文件就地统计，子目录交给 rayon 并行处理（工作窃取）
+///
+/// `depth` 为 `dir` 相对扫描根目录的层级；`ancestors` 仅在跟随符号链接时记录
+/// 当前路径上各目录的真实路径，用于发现循环。
+fn scan_dir_recursive<F>(
+    ctx: &ScanContext<'_, F>,
+    dir: &Path,
+    depth: usize,
+    ancestors: &[PathBuf],
+) -> ScanPartial
+where
+    F: Fn(FileStatsProgress) + Sync,
+{
+    let mut partial = ScanPartial::default();
+    if ctx.is_cancelled() {
+        return partial;
+    }
+
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(error) => {
+            let reason = format!("无法读取目录: {}", error);
+            ctx.record_skipped(&mut partial, Some(dir), &error, reason);
+            return partial;
+        }
+    };
+
+    let options = ctx.options;
+    let can_descend = !matches!(options.max_depth, Some(max_depth) if depth + 2 > max_depth);
+    let mut subdirs = Vec::new();
+    let mut folded_names: HashMap<String, Vec<PathBuf>> = HashMap::new();
+    let mut version_candidates: HashMap<String, Vec<VersionFile>> = HashMap::new();
+    for entry_result in entries {
+        if ctx.is_cancelled() {
+            return partial;
         }
 
         let entry = match entry_result {
             Ok(entry) => entry,
             Err(error) => {
-                skipped_files += 1;
-                if error
-                    .io_error()
-                    .is_some_and(|io_error| io_error.kind() == std::io::ErrorKind::PermissionDenied)
-                {
-                    permission_denied_files += 1;
-                }
-                push_scan_issue(&mut sample_errors, error.path(), error.to_string());
+                let reason = format!("无法读取目录项: {}", error);
+                ctx.record_skipped(&mut partial, Some(dir), &error, reason);
                 continue;
             }
         };
+        let entry_path = entry.path();
+        let entry_name = entry.file_name();
 
-        if entry.file_type().is_dir() {
-            if entry.depth() > 0 {
-                folder_count += 1;
-            }
+        if options.skip_hidden && has_hidden_name(&entry_name) {
+            partial.excluded.hidden += 1;
             continue;
         }
-
-        if !entry.file_type().is_file() {
+        if ctx.is_excluded_by_glob(&entry_path, &entry_name) {
+            partial.excluded.by_glob += 1;
             continue;
         }
+        if options.check_health {
+            partial.health.check_name(&entry_path, &entry_name);
+            folded_names
+                .entry(entry_name.to_string_lossy().to_lowercase())
+                .or_default()
+                .push(entry_path.clone());
+        }
 
-        let metadata = match entry.metadata() {
+        // DirEntry::metadata 不跟随符号链接，与 du 的统计口径一致
+        let mut metadata = match entry.metadata() {
             Ok(metadata) => metadata,
             Err(error) => {
-                skipped_files += 1;
-                if error
-                    .io_error()
-                    .is_some_and(|io_error| io_error.kind() == std::io::ErrorKind::PermissionDenied)
-                {
-                    permission_denied_files += 1;
-                }
-                push_scan_issue(
-                    &mut sample_errors,
-                    Some(entry.path()),
-                    format!("无法读取文件信息: {}", error),
-                );
+                let reason = format!("无法读取文件信息: {}", error);
+                ctx.record_skipped(&mut partial, Some(&entry_path), &error, reason);
                 continue;
             }
         };
 
-        let ext = normalize_extension(entry.path());
-        let size = get_file_size(&metadata);
-
-        let stat = stats.entry(ext).or_insert((0, 0));
-        stat.0 += 1;
-        stat.1 += size;
-
-        total_files += 1;
-        total_size += size;
-
-        let processed_total = total_files.saturating_add(skipped_files);
-        if processed_total == 1 || last_progress_emit.elapsed() >= Duration::from_millis(200) {
-            last_progress_emit = Instant::now();
-            emit_progress(
-                &mut emit,
-                task_id,
-                "扫描文件".into(),
-                total_files,
-                0,
-                0.0,
-                scan_start.elapsed(),
-                skipped_files,
-                permission_denied_files,
-            );
+        if options.skip_hidden && has_hidden_attribute(&metadata) {
+            partial.excluded.hidden += 1;
+            continue;
+        }
+
+        // 失效的符号链接保留链接自身的信息
+        if metadata.file_type().is_symlink() && (options.follow_symlinks || options.check_health) {
+            match std::fs::metadata(&entry_path) {
+                Ok(target_metadata) if options.follow_symlinks => metadata = target_metadata,
+                Ok(_) => {}
+                Err(error) if options.check_health && !is_permission_denied(&error) => {
+                    partial.health.record_broken_symlink(&entry_path);
+                }
+                Err(_) => {}
+            }
+        }
+
+        if metadata.is_file() {
+            scan_file(ctx, &mut partial, &entry_path, &metadata);
+            if options.detect_versions {
+                if let Some(name) = entry_name.to_str() {
+                    version_candidates
+                        .entry(version_key(name))
+                        .or_default()
+                        .push(version_file(&entry_path, &metadata));
+                }
+            }
+            continue;
+        }
+
+        // du 也会计入目录和符号链接自身占用的块
+        partial.total_allocated_size += get_allocated_size(&metadata);
+        if !metadata.is_dir() {
+            continue;
+        }
+
+        partial.folder_count += 1;
+        if !can_descend {
+            partial.excluded.by_depth += 1;
+            continue;
+        }
+        if options.one_filesystem && device_id(&metadata) != ctx.root_device {
+            partial.excluded.other_filesystem += 1;
+            continue;
         }
+        subdirs.push(entry_path);
+    }
+    partial.health.check_case_collisions(folded_names);
+    partial.versions.add_directory(dir, version_candidates);
+
+    subdirs
+        .par_iter()
+        .map(|subdir| {
+            if !options.follow_symlinks {
+                return scan_dir_recursive(ctx, subdir, depth + 1, ancestors);
+            }
+
+            let canonical = std::fs::canonicalize(subdir).unwrap_or_else(|_| subdir.clone());
+            if ancestors.contains(&canonical) {
+                let mut partial = ScanPartial::default();
+                partial.excluded.symlink_loops += 1;
+                push_scan_issue(
+                    &mut partial.sample_errors,
+                    Some(subdir),
+                    format!("检测到符号链接循环，指向 {}", canonical.display()),
+                );
+                return partial;
+            }
+
+            let mut chain = ancestors.to_vec();
+            chain.push(canonical);
+            scan_dir_recursive(ctx, subdir, depth + 1, &chain)
+        })
+        .reduce(ScanPartial::default, ScanPartial::merge)
+        .merge(partial)
+}
+
+fn scan_directory_inner<F>(
+    path: &str,
+    task_id: &str,
+    options: &ScanOptions,
+    cancelled: &AtomicBool,
+    emit: F,
+) -> Result<ScanResult, String>
+where
+    F: Fn(FileStatsProgress) + Sync,
+{
+    let root_metadata =
+        std::fs::metadata(path).map_err(|error| format!("无法访问所选文件夹: {}", error))?;
+    if !root_metadata.is_dir() {
+        return Err("请选择文件夹，而不是单个文件".into());
     }
+    if options.max_depth == Some(0) {
+        return Err("最大扫描深度至少为 1".into());
+    }
+    let exclude_patterns = compile_exclude_patterns(&options.exclude_globs)?;
+    let root = Path::new(path);
+    let root_ancestors = if options.follow_symlinks {
+        vec![std::fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf())]
+    } else {
+        Vec::new()
+    };
 
-    let mut result: Vec<FileStats> = stats
+    let scanned_at = chrono::Local::now();
+    let scan_start = Instant::now();
+    let ctx = ScanContext {
+        task_id,
+        root,
+        options,
+        exclude_patterns,
+        root_device: device_id(&root_metadata),
+        cancelled,
+        emit: &emit,
+        scan_start,
+        last_progress_emit: Mutex::new(None),
+        scanned_files: AtomicU64::new(0),
+        skipped_files: AtomicU64::new(0),
+        permission_denied_files: AtomicU64::new(0),
+        seen_hard_links: Mutex::new(HashSet::new()),
+    };
+
+    emit_progress(
+        &emit,
+        task_id,
+        "扫描文件".into(),
+        0,
+        0,
+        0.0,
+        scan_start.elapsed(),
+        0,
+        0,
+    );
+
+    let mut partial = scan_dir_recursive(&ctx, root, 0, &root_ancestors);
+    if cancelled.load(Ordering::Relaxed) {
+        return Err("操作已取消".to_string());
+    }
+    partial.total_allocated_size += get_allocated_size(&root_metadata);
+
+    let skipped_files = ctx.skipped_files.load(Ordering::Relaxed);
+    let permission_denied_files = ctx.permission_denied_files.load(Ordering::Relaxed);
+    let ScanPartial {
+        stats,
+        total_files,
+        folder_count,
+        total_size,
+        total_allocated_size,
+        sparse_files,
+        mut sparse_samples,
+        excluded,
+        ownership,
+        health,
+        versions,
+        sample_errors,
+    } = partial;
+
+    let mut entries: Vec<(FileStats, CompressionCandidates)> = stats
         .into_iter()
-        .map(|(ext, (count, size))| FileStats {
-            extension: if ext.is_empty() {
-                "(无扩展名)".into()
-            } else {
-                format!(".{}", ext)
-            },
-            count,
-            total_size: size,
+        .map(|(ext, totals)| {
+            let stat = FileStats {
+                extension: if ext.is_empty() {
+                    "(无扩展名)".into()
+                } else {
+                    format!(".{}", ext)
+                },
+                count: totals.count,
+                total_size: totals.size,
+                allocated_size: totals.allocated_size,
+                sparse_count: totals.sparse_count,
+                compression: None,
+            };
+            (stat, totals.compression_candidates)
         })
         .collect();
 
-    result.sort_by(|a, b| {
+    entries.sort_by(|(a, _), (b, _)| {
         b.total_size
             .cmp(&a.total_size)
             .then_with(|| b.count.cmp(&a.count))
             .then_with(|| a.extension.cmp(&b.extension))
     });
 
+    if options.estimate_compression {
+        emit_progress(
+            &emit,
+            task_id,
+            "估算压缩率".into(),
+            total_files,
+            total_files.saturating_add(skipped_files).max(1),
+            99.0,
+            scan_start.elapsed(),
+            skipped_files,
+            permission_denied_files,
+        );
+        // 只估算占用最大的几种类型，读取量有上限
+        entries
+            .par_iter_mut()
+            .take(MAX_COMPRESSION_TYPES)
+            .for_each(|(stat, candidates)| {
+                stat.compression = candidates.estimate(stat.total_size, cancelled);
+            });
+        if cancelled.load(Ordering::Relaxed) {
+            return Err("操作已取消".to_string());
+        }
+    }
+    let result: Vec<FileStats> = entries.into_iter().map(|(stat, _)| stat).collect();
+    sparse_samples.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+
     let type_count = result.len();
     let processed_total = total_files.saturating_add(skipped_files);
     emit_progress(
-        &mut emit,
+        &emit,
         task_id,
         format!("扫描完成，耗时 {:.1}s", scan_start.elapsed().as_secs_f64()),
         total_files,
@@ -260,39 +736,149 @@ where
     );
 
     Ok(ScanResult {
+        root_path: path.to_string(),
+        scanned_at: scanned_at.to_rfc3339(),
         stats: result,
         total_files,
         folder_count,
         total_size,
+        total_allocated_size,
         type_count,
+        sparse_files,
+        sparse_samples,
         skipped_files,
         permission_denied_files,
+        excluded,
+        ownership: options.include_ownership.then(|| ownership.into_report()),
+        health: options.check_health.then_some(health),
+        versions: options.detect_versions.then(|| versions.finish()),
+        options: options.clone(),
         sample_errors,
     })
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Default)]
+struct ExtensionTotals {
+    count: u64,
+    size: u64,
+    allocated_size: u64,
+    sparse_count: u64,
+    compression_candidates: CompressionCandidates,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct FileStats {
     pub extension: String,
     pub count: u64,
     pub total_size: u64,
+    pub allocated_size: u64,
+    pub sparse_count: u64,
+    /// 仅在开启 `estimate_compression` 时返回，且只估算占用最大的前几种类型
+    pub compression: Option<CompressionEstimate>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SparseFile {
+    pub path: String,
+    pub size: u64,
+    pub allocated_size: u64,
+}
+
+/// 扫描选项，所有字段均可省略
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScanOptions {
+    /// 排除规则（glob），匹配文件名或相对扫描根目录的路径，例如 `node_modules`、`*.tmp`、`.git`
+    pub exclude_globs: Vec<String>,
+    /// 最大扫描深度，1 表示只统计根目录下的直接子项
+    pub max_depth: Option<usize>,
+    /// 跳过隐藏文件和目录（以 . 开头，Windows 上还包括带隐藏属性的项目）
+    pub skip_hidden: bool,
+    /// 不跨越挂载点，只统计与扫描根目录位于同一文件系统的内容（仅 Unix）
+    pub one_filesystem: bool,
+    /// 跟随符号链接，遇到指向上层目录的链接会跳过并记录
+    pub follow_symlinks: bool,
+    /// 按属主/属组汇总并检查危险权限（仅 Unix，每个文件多一次 access 调用）
+    pub include_ownership: bool,
+    /// 检查失效链接、超长路径、非法文件名等会影响跨系统复制的问题
+    pub check_health: bool,
+    /// 对每种类型抽样少量数据块做快速压缩，估算压缩率和可节省的空间
+    pub estimate_compression: bool,
+    /// 查找同一目录下疑似同一文档的多个版本，例如 `report_final (2).docx`
+    pub detect_versions: bool,
+}
+
+/// 因扫描选项被排除的项目数（被排除的目录只计一次，不含其内容）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScanExclusions {
+    pub by_glob: u64,
+    pub hidden: u64,
+    pub by_depth: u64,
+    pub other_filesystem: u64,
+    pub symlink_loops: u64,
 }
 
-#[derive(Debug, Serialize)]
+/// 命中某条检查规则的项目：完整计数加有限数量的路径
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FlaggedPaths {
+    pub count: u64,
+    pub paths: Vec<String>,
+    /// 最多保留的路径数量，由各项检查自行决定
+    #[serde(skip)]
+    limit: usize,
+}
+
+impl FlaggedPaths {
+    pub(crate) fn new(limit: usize) -> Self {
+        Self {
+            count: 0,
+            paths: Vec::new(),
+            limit,
+        }
+    }
+
+    pub(crate) fn push(&mut self, path: &Path) {
+        self.count += 1;
+        if self.paths.len() < self.limit {
+            self.paths.push(path.display().to_string());
+        }
+    }
+
+    pub(crate) fn merge(&mut self, other: FlaggedPaths) {
+        self.count += other.count;
+        let room = self.limit.saturating_sub(self.paths.len());
+        self.paths.extend(other.paths.into_iter().take(room));
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ScanIssue {
     pub path: String,
     pub reason: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ScanResult {
+    pub root_path: String,
+    pub scanned_at: String,
     pub stats: Vec<FileStats>,
     pub total_files: u64,
     pub folder_count: u64,
     pub total_size: u64,
+    pub total_allocated_size: u64,
     pub type_count: usize,
+    pub sparse_files: u64,
+    pub sparse_samples: Vec<SparseFile>,
     pub skipped_files: u64,
     pub permission_denied_files: u64,
+    pub excluded: ScanExclusions,
+    /// 仅在开启 `include_ownership` 时返回
+    pub ownership: Option<OwnershipReport>,
+    /// 仅在开启 `check_health` 时返回
+    pub health: Option<HealthReport>,
+    /// 仅在开启 `detect_versions` 时返回
+    pub versions: Option<VersionReport>,
+    pub options: ScanOptions,
     pub sample_errors: Vec<ScanIssue>,
 }
 
@@ -314,12 +900,16 @@ pub async fn scan_directory(
     app: AppHandle,
     path: String,
     task_id: String,
+    options: Option<ScanOptions>,
 ) -> Result<ScanResult, String> {
+    let _foreground = ForegroundScanGuard::enter();
+    preempt_background_tasks();
     let cancelled = register_task(&task_id);
     let task_id_for_cleanup = task_id.clone();
+    let options = options.unwrap_or_default();
 
     let task_result = tokio::task::spawn_blocking(move || {
-        scan_directory_inner(&path, &task_id, &cancelled, |progress| {
+        scan_directory_inner(&path, &task_id, &options, &cancelled, |progress| {
             let _ = app.emit("file-stats-progress", progress);
         })
     })
@@ -337,6 +927,30 @@ pub fn cancel_file_stats(task_id: String) {
     mark_task_cancelled(&task_id);
 }
 
+/// 在当前线程执行一次后台扫描，不上报进度；用户发起扫描时会被取消
+pub(crate) fn run_background_scan(
+    path: &str,
+    name: &str,
+    options: &ScanOptions,
+) -> Result<ScanResult, BackgroundScanError> {
+    let task_id = format!("{}{}", BACKGROUND_TASK_PREFIX, name);
+    let cancelled = register_task(&task_id);
+    // 调度器检查之后、注册之前开始的用户扫描抢占不到这个任务，注册后再确认一次
+    if has_foreground_scan() {
+        cleanup_task(&task_id);
+        return Err(BackgroundScanError::Cancelled);
+    }
+    let result = scan_directory_inner(path, &task_id, options, &cancelled, |_| {});
+    cleanup_task(&task_id);
+    result.map_err(|error| {
+        if cancelled.load(Ordering::Relaxed) {
+            BackgroundScanError::Cancelled
+        } else {
+            BackgroundScanError::Failed(error)
+        }
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -389,6 +1003,7 @@ mod tests {
         let result = scan_directory_inner(
             temp_dir.path().to_str().expect("invalid temp dir path"),
             "task-test",
+            &ScanOptions::default(),
             &cancelled,
             |_| {},
         )
@@ -417,6 +1032,539 @@ mod tests {
         assert_eq!(no_extension.total_size, 4);
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn scan_directory_reports_allocated_size_and_sparse_files() {
+        let temp_dir = TestDir::new();
+        let sparse_path = temp_dir.path().join("disk.img");
+        let sparse_file = fs::File::create(&sparse_path).expect("failed to create sparse file");
+        sparse_file
+            .set_len(64 * 1024 * 1024)
+            .expect("failed to extend sparse file");
+        drop(sparse_file);
+        fs::write(temp_dir.path().join("a.txt"), b"hello").expect("failed to write test file");
+
+        let cancelled = AtomicBool::new(false);
+        let result = scan_directory_inner(
+            temp_dir.path().to_str().expect("invalid temp dir path"),
+            "task-test",
+            &ScanOptions::default(),
+            &cancelled,
+            |_| {},
+        )
+        .expect("scan should succeed");
+
+        assert_eq!(result.total_size, 64 * 1024 * 1024 + 5);
+        assert!(result.total_allocated_size < result.total_size);
+        assert_eq!(result.sparse_files, 1);
+        assert_eq!(result.sparse_samples.len(), 1);
+        assert!(result.sparse_samples[0].path.ends_with("disk.img"));
+
+        let img = result
+            .stats
+            .iter()
+            .find(|item| item.extension == ".img")
+            .expect("img stats should exist");
+        assert_eq!(img.sparse_count, 1);
+        assert!(img.allocated_size < img.total_size);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn scan_directory_counts_hard_links_once_in_allocated_size() {
+        let temp_dir = TestDir::new();
+        let original = temp_dir.path().join("a.bin");
+        fs::write(&original, vec![7_u8; 256 * 1024]).expect("failed to write test file");
+
+        let cancelled = AtomicBool::new(false);
+        let before = scan_directory_inner(
+            temp_dir.path().to_str().expect("invalid temp dir path"),
+            "task-test",
+            &ScanOptions::default(),
+            &cancelled,
+            |_| {},
+        )
+        .expect("scan should succeed");
+
+        fs::hard_link(&original, temp_dir.path().join("b.bin")).expect("failed to create link");
+        let after = scan_directory_inner(
+            temp_dir.path().to_str().expect("invalid temp dir path"),
+            "task-test",
+            &ScanOptions::default(),
+            &cancelled,
+            |_| {},
+        )
+        .expect("scan should succeed");
+
+        assert_eq!(after.total_files, 2);
+        assert_eq!(after.total_size, before.total_size * 2);
+        assert_eq!(after.total_allocated_size, before.total_allocated_size);
+    }
+
+    fn generate_tree(root: &Path, depth: usize, dirs_per_level: usize, files_per_dir: usize) {
+        for index in 0..files_per_dir {
+            let ext = ["txt", "jpg", "log"][index % 3];
+            fs::write(
+                root.join(format!("file-{}.{}", index, ext)),
+                vec![b'x'; index * 10 + 1],
+            )
+            .expect("failed to write generated file");
+        }
+        if depth == 0 {
+            return;
+        }
+        for index in 0..dirs_per_level {
+            let child = root.join(format!("dir-{}", index));
+            fs::create_dir_all(&child).expect("failed to create generated dir");
+            generate_tree(&child, depth - 1, dirs_per_level, files_per_dir);
+        }
+    }
+
+    fn scan_sequentially(root: &Path) -> (u64, u64, u64) {
+        let mut files = 0;
+        let mut folders = 0;
+        let mut size = 0;
+        for entry in walkdir::WalkDir::new(root).min_depth(1) {
+            let entry = entry.expect("generated tree should be readable");
+            if entry.file_type().is_dir() {
+                folders += 1;
+            } else if entry.file_type().is_file() {
+                files += 1;
+                size += entry.metadata().expect("metadata should be readable").len();
+            }
+        }
+        (files, folders, size)
+    }
+
+    #[test]
+    fn parallel_scan_matches_sequential_walk_on_generated_tree() {
+        let temp_dir = TestDir::new();
+        generate_tree(temp_dir.path(), 3, 4, 6);
+        let (expected_files, expected_folders, expected_size) = scan_sequentially(temp_dir.path());
+
+        let progress_events = Mutex::new(Vec::new());
+        let cancelled = AtomicBool::new(false);
+        let result = scan_directory_inner(
+            temp_dir.path().to_str().expect("invalid temp dir path"),
+            "task-test",
+            &ScanOptions::default(),
+            &cancelled,
+            |progress| {
+                progress_events
+                    .lock()
+                    .expect("progress lock poisoned")
+                    .push(progress)
+            },
+        )
+        .expect("scan should succeed");
+
+        assert_eq!(result.total_files, expected_files);
+        assert_eq!(result.folder_count, expected_folders);
+        assert_eq!(result.total_size, expected_size);
+        assert_eq!(result.type_count, 3);
+        assert_eq!(
+            result.stats.iter().map(|item| item.count).sum::<u64>(),
+            expected_files
+        );
+
+        let progress_events = progress_events
+            .into_inner()
+            .expect("progress lock poisoned");
+        let last = progress_events.last().expect("progress should be emitted");
+        assert_eq!(last.percent, 100.0);
+        assert_eq!(last.current, expected_files);
+    }
+
+    /// cargo test scan_directory_benchmark -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn scan_directory_benchmark_on_generated_tree() {
+        let temp_dir = TestDir::new();
+        generate_tree(temp_dir.path(), 4, 6, 20);
+
+        let sequential_start = Instant::now();
+        let (expected_files, _, _) = scan_sequentially(temp_dir.path());
+        let sequential_elapsed = sequential_start.elapsed();
+
+        let cancelled = AtomicBool::new(false);
+        let parallel_start = Instant::now();
+        let result = scan_directory_inner(
+            temp_dir.path().to_str().expect("invalid temp dir path"),
+            "task-bench",
+            &ScanOptions::default(),
+            &cancelled,
+            |_| {},
+        )
+        .expect("scan should succeed");
+        let parallel_elapsed = parallel_start.elapsed();
+
+        assert_eq!(result.total_files, expected_files);
+        println!(
+            "{} files / {} folders: walkdir {:?}, parallel scan {:?}",
+            result.total_files, result.folder_count, sequential_elapsed, parallel_elapsed
+        );
+    }
+
+    #[test]
+    fn scan_directory_applies_exclusions_and_echoes_options() {
+        let temp_dir = TestDir::new();
+        let node_modules = temp_dir.path().join("node_modules").join("pkg");
+        let nested = temp_dir.path().join("src").join("deep").join("deeper");
+        fs::create_dir_all(&node_modules).expect("failed to create node_modules");
+        fs::create_dir_all(&nested).expect("failed to create nested dir");
+        fs::create_dir_all(temp_dir.path().join(".git")).expect("failed to create .git");
+        fs::write(node_modules.join("index.js"), b"module").expect("failed to write test file");
+        fs::write(temp_dir.path().join(".git").join("HEAD"), b"ref").expect("failed to write");
+        fs::write(temp_dir.path().join(".env"), b"secret").expect("failed to write test file");
+        fs::write(temp_dir.path().join("build.tmp"), b"tmp").expect("failed to write test file");
+        fs::write(temp_dir.path().join("src").join("main.rs"), b"fn main() {}")
+            .expect("failed to write test file");
+        fs::write(nested.join("too-deep.rs"), b"//").expect("failed to write test file");
+
+        let options = ScanOptions {
+            exclude_globs: vec!["node_modules".into(), "*.tmp".into()],
+            max_depth: Some(2),
+            skip_hidden: true,
+            ..Default::default()
+        };
+        let cancelled = AtomicBool::new(false);
+        let result = scan_directory_inner(
+            temp_dir.path().to_str().expect("invalid temp dir path"),
+            "task-test",
+            &options,
+            &cancelled,
+            |_| {},
+        )
+        .expect("scan should succeed");
+
+        assert_eq!(result.total_files, 1);
+        assert_eq!(result.stats[0].extension, ".rs");
+        assert_eq!(result.excluded.by_glob, 2);
+        assert_eq!(result.excluded.hidden, 2);
+        assert_eq!(result.excluded.by_depth, 1);
+        assert_eq!(result.folder_count, 2);
+        assert_eq!(result.options.exclude_globs, options.exclude_globs);
+        assert_eq!(result.options.max_depth, Some(2));
+    }
+
+    #[test]
+    fn scan_directory_rejects_invalid_exclude_globs() {
+        let temp_dir = TestDir::new();
+        let options = ScanOptions {
+            exclude_globs: vec!["[".into()],
+            ..Default::default()
+        };
+
+        let cancelled = AtomicBool::new(false);
+        let error = scan_directory_inner(
+            temp_dir.path().to_str().expect("invalid temp dir path"),
+            "task-test",
+            &options,
+            &cancelled,
+            |_| {},
+        )
+        .expect_err("scan should reject invalid glob");
+
+        assert!(error.contains("无效的排除规则"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn scan_directory_follows_symlinks_without_looping() {
+        let temp_dir = TestDir::new();
+        let data = temp_dir.path().join("data");
+        let outside = TestDir::new();
+        fs::create_dir_all(&data).expect("failed to create data dir");
+        fs::write(data.join("a.txt"), b"hello").expect("failed to write test file");
+        fs::write(outside.path().join("b.txt"), b"world").expect("failed to write test file");
+        std::os::unix::fs::symlink(temp_dir.path(), data.join("loop"))
+            .expect("failed to create loop symlink");
+        std::os::unix::fs::symlink(outside.path(), data.join("linked"))
+            .expect("failed to create dir symlink");
+
+        let options = ScanOptions {
+            follow_symlinks: true,
+            ..Default::default()
+        };
+        let cancelled = AtomicBool::new(false);
+        let result = scan_directory_inner(
+            temp_dir.path().to_str().expect("invalid temp dir path"),
+            "task-test",
+            &options,
+            &cancelled,
+            |_| {},
+        )
+        .expect("scan should succeed");
+
+        assert_eq!(result.total_files, 2);
+        assert_eq!(result.total_size, 10);
+        assert_eq!(result.excluded.symlink_loops, 1);
+        assert!(result
+            .sample_errors
+            .iter()
+            .any(|issue| issue.reason.contains("符号链接循环")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn scan_directory_reports_ownership_and_risky_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TestDir::new();
+        let shared = temp_dir.path().join("shared.txt");
+        let tool = temp_dir.path().join("tool");
+        let secret = temp_dir.path().join("secret.txt");
+        fs::write(&shared, b"shared").expect("failed to write test file");
+        fs::write(&tool, b"binary").expect("failed to write test file");
+        fs::write(&secret, b"secret").expect("failed to write test file");
+        fs::set_permissions(&shared, fs::Permissions::from_mode(0o666))
+            .expect("failed to chmod shared file");
+        fs::set_permissions(&tool, fs::Permissions::from_mode(0o4755))
+            .expect("failed to chmod tool");
+        fs::set_permissions(&secret, fs::Permissions::from_mode(0o000))
+            .expect("failed to chmod secret file");
+
+        let options = ScanOptions {
+            include_ownership: true,
+            ..Default::default()
+        };
+        let cancelled = AtomicBool::new(false);
+        let result = scan_directory_inner(
+            temp_dir.path().to_str().expect("invalid temp dir path"),
+            "task-test",
+            &options,
+            &cancelled,
+            |_| {},
+        )
+        .expect("scan should succeed");
+        let _ = fs::set_permissions(&secret, fs::Permissions::from_mode(0o600));
+
+        let ownership = result.ownership.expect("ownership report should exist");
+        let uid = unsafe { libc::geteuid() };
+        assert_eq!(ownership.by_user.len(), 1);
+        assert_eq!(ownership.by_user[0].id, uid);
+        assert_eq!(ownership.by_user[0].count, 3);
+        assert_eq!(ownership.by_user[0].total_size, 18);
+        assert_eq!(
+            ownership
+                .by_group
+                .iter()
+                .map(|item| item.count)
+                .sum::<u64>(),
+            3
+        );
+        assert_eq!(ownership.world_writable.count, 1);
+        assert!(ownership.world_writable.paths[0].ends_with("shared.txt"));
+        assert_eq!(ownership.setuid.count, 1);
+        assert!(ownership.setuid.paths[0].ends_with("tool"));
+        // root 可以读取任何文件
+        if uid != 0 {
+            assert_eq!(ownership.unreadable.count, 1);
+        }
+    }
+
+    // macOS 默认的 APFS 不区分大小写且拒绝非 UTF-8 文件名
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn scan_directory_health_pass_categorizes_problem_paths() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let temp_dir = TestDir::new();
+        fs::write(temp_dir.path().join("Report.txt"), b"a").expect("failed to write test file");
+        fs::write(temp_dir.path().join("report.TXT"), b"b").expect("failed to write test file");
+        fs::write(temp_dir.path().join("a:b.txt"), b"c").expect("failed to write test file");
+        fs::write(temp_dir.path().join("notes "), b"d").expect("failed to write test file");
+        fs::write(
+            temp_dir
+                .path()
+                .join(std::ffi::OsStr::from_bytes(b"caf\xe9.txt")),
+            b"e",
+        )
+        .expect("failed to write test file");
+        std::os::unix::fs::symlink(
+            temp_dir.path().join("missing"),
+            temp_dir.path().join("dangling"),
+        )
+        .expect("failed to create symlink");
+
+        let options = ScanOptions {
+            check_health: true,
+            ..Default::default()
+        };
+        let cancelled = AtomicBool::new(false);
+        let result = scan_directory_inner(
+            temp_dir.path().to_str().expect("invalid temp dir path"),
+            "task-test",
+            &options,
+            &cancelled,
+            |_| {},
+        )
+        .expect("scan should succeed");
+
+        let health = result.health.expect("health report should exist");
+        assert_eq!(health.broken_symlinks.count, 1);
+        assert!(health.broken_symlinks.paths[0].ends_with("dangling"));
+        assert_eq!(health.case_collisions.count, 2);
+        assert_eq!(health.invalid_names.count, 1);
+        assert_eq!(health.trailing_spaces.count, 1);
+        assert_eq!(health.non_utf8_names.count, 1);
+        assert_eq!(health.long_paths.count, 0);
+    }
+
+    #[test]
+    fn scan_directory_omits_optional_sections_by_default() {
+        let temp_dir = TestDir::new();
+        fs::write(temp_dir.path().join("a.txt"), b"hello").expect("failed to write test file");
+
+        let cancelled = AtomicBool::new(false);
+        let result = scan_directory_inner(
+            temp_dir.path().to_str().expect("invalid temp dir path"),
+            "task-test",
+            &ScanOptions::default(),
+            &cancelled,
+            |_| {},
+        )
+        .expect("scan should succeed");
+
+        assert!(result.ownership.is_none());
+        assert!(result.health.is_none());
+        assert!(result.stats.iter().all(|item| item.compression.is_none()));
+    }
+
+    #[test]
+    fn scan_directory_clusters_document_versions_per_directory() {
+        let temp_dir = TestDir::new();
+        let docs = temp_dir.path().join("docs");
+        fs::create_dir_all(&docs).expect("failed to create docs dir");
+        fs::write(docs.join("report_final.docx"), vec![0u8; 100]).expect("failed to write");
+        fs::write(docs.join("report_final (2).docx"), vec![0u8; 120]).expect("failed to write");
+        fs::write(docs.join("report_final_v3_REAL.docx"), vec![0u8; 150]).expect("failed to write");
+        fs::write(docs.join("report.pdf"), vec![0u8; 80]).expect("failed to write");
+        fs::write(docs.join("photo_001.jpg"), b"a").expect("failed to write");
+        fs::write(docs.join("photo_002.jpg"), b"b").expect("failed to write");
+        // 不同目录下的同名文件不归为一簇
+        fs::write(temp_dir.path().join("report_v2.docx"), vec![0u8; 90]).expect("failed to write");
+
+        let cancelled = AtomicBool::new(false);
+        let result = scan_directory_inner(
+            temp_dir.path().to_str().expect("invalid temp dir path"),
+            "task-test",
+            &ScanOptions {
+                detect_versions: true,
+                ..ScanOptions::default()
+            },
+            &cancelled,
+            |_| {},
+        )
+        .expect("scan should succeed");
+
+        let versions = result.versions.expect("version report should exist");
+        assert_eq!(versions.cluster_count, 1);
+        assert_eq!(versions.file_count, 3);
+        let cluster = &versions.clusters[0];
+        assert_eq!(cluster.base_name, "report.docx");
+        assert_eq!(cluster.total_size, 370);
+        assert_eq!(
+            cluster.reclaimable_size,
+            cluster.total_size - cluster.files[0].size
+        );
+        assert!(cluster.files.iter().all(|file| file.modified.is_some()));
+    }
+
+    #[test]
+    fn scan_directory_estimates_compression_per_type() {
+        let temp_dir = TestDir::new();
+        let text = "2024-05-01 INFO request handled\n".repeat(8 * 1024);
+        for index in 0..3 {
+            fs::write(temp_dir.path().join(format!("app-{}.log", index)), &text)
+                .expect("failed to write log file");
+        }
+        // 伪随机数据，模拟已压缩的内容
+        let noise: Vec<u8> = (0..256 * 1024u64)
+            .flat_map(|index| xxhash_rust::xxh3::xxh3_64(&index.to_le_bytes()).to_le_bytes())
+            .collect();
+        fs::write(temp_dir.path().join("archive.zip"), &noise).expect("failed to write zip file");
+        fs::write(temp_dir.path().join("empty.dat"), b"").expect("failed to write empty file");
+
+        let cancelled = AtomicBool::new(false);
+        let result = scan_directory_inner(
+            temp_dir.path().to_str().expect("invalid temp dir path"),
+            "task-test",
+            &ScanOptions {
+                estimate_compression: true,
+                ..ScanOptions::default()
+            },
+            &cancelled,
+            |_| {},
+        )
+        .expect("scan should succeed");
+
+        let find = |extension: &str| {
+            result
+                .stats
+                .iter()
+                .find(|item| item.extension == extension)
+                .expect("extension should be present")
+        };
+        let log = find(".log").compression.as_ref().expect("log estimate");
+        assert_eq!(log.sampled_files, 3);
+        assert!(log.ratio < 0.2, "log ratio {}", log.ratio);
+        assert!(log.projected_savings > find(".log").total_size / 2);
+
+        let zip = find(".zip").compression.as_ref().expect("zip estimate");
+        assert!(zip.ratio > 0.95, "zip ratio {}", zip.ratio);
+        // 大文件只读取少量块
+        assert!(zip.sampled_bytes < find(".zip").total_size / 4);
+
+        assert!(find(".dat").compression.is_none());
+    }
+
+    #[test]
+    fn flagged_paths_keep_their_own_limit() {
+        let mut left = FlaggedPaths::new(2);
+        let mut right = FlaggedPaths::new(2);
+        for name in ["a", "b", "c"] {
+            left.push(Path::new(name));
+            right.push(Path::new(name));
+        }
+        left.merge(right);
+        assert_eq!(left.count, 6);
+        assert_eq!(left.paths, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn user_scans_preempt_background_scans_only() {
+        let background = register_task("background-test-preempt");
+        let foreground = register_task("task-test-preempt");
+
+        preempt_background_tasks();
+
+        assert!(background.load(Ordering::Relaxed));
+        assert!(!foreground.load(Ordering::Relaxed));
+        cleanup_task("background-test-preempt");
+        cleanup_task("task-test-preempt");
+    }
+
+    #[test]
+    fn stale_cancel_requests_do_not_block_background_scans() {
+        // 扫描结束后才到达的取消请求会在取消表里留下键
+        cancel_file_stats("task-test-finished".into());
+        assert!(!has_foreground_scan());
+
+        let temp_dir = TestDir::new();
+        let path = temp_dir.path().to_str().expect("invalid temp path");
+        let guard = ForegroundScanGuard::enter();
+        assert!(has_foreground_scan());
+        assert_eq!(
+            run_background_scan(path, "test-race", &ScanOptions::default()).err(),
+            Some(BackgroundScanError::Cancelled)
+        );
+        drop(guard);
+        assert!(run_background_scan(path, "test-race", &ScanOptions::default()).is_ok());
+        assert!(!has_foreground_scan());
+        cleanup_task("task-test-finished");
+    }
+
     #[test]
     fn scan_directory_rejects_regular_files() {
         let temp_dir = TestDir::new();
@@ -427,6 +1575,7 @@ mod tests {
         let error = scan_directory_inner(
             file_path.to_str().expect("invalid file path"),
             "task-test",
+            &ScanOptions::default(),
             &cancelled,
             |_| {},
         )
@@ -444,6 +1593,7 @@ mod tests {
         let error = scan_directory_inner(
             temp_dir.path().to_str().expect("invalid temp dir path"),
             "task-test",
+            &ScanOptions::default(),
             &cancelled,
             |_| {},
         )
@@ -0,0 +1,476 @@
+use filetime::FileTime;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufReader, BufWriter, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use super::dedup::remove_file_path;
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CleanupAction {
+    /// 移到回收站
+    Trash,
+    /// 永久删除
+    Delete,
+    /// 移动到 `destination` 目录，重名时自动加编号
+    Move,
+    /// 在原位置压缩为 `.gz` 并删除原文件
+    Compress,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CleanupItemResult {
+    pub path: String,
+    /// 移动或压缩后的新路径
+    pub target_path: Option<String>,
+    /// success / planned（预演）/ skipped / failed
+    pub status: String,
+    pub message: String,
+    /// 原位置释放的字节数，压缩时为原大小与压缩后大小之差
+    pub reclaimed_size: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CleanupResult {
+    pub action: CleanupAction,
+    pub dry_run: bool,
+    pub success_count: u32,
+    pub skipped_count: u32,
+    pub failed_count: u32,
+    pub reclaimed_size: u64,
+    pub items: Vec<CleanupItemResult>,
+}
+
+/// 在目录中找一个不冲突的文件名：`name.ext`、`name (1).ext`……
+fn unique_target_path(dir: &Path, file_name: &str) -> PathBuf {
+    let candidate = dir.join(file_name);
+    if !candidate.exists() {
+        return candidate;
+    }
+
+    let source = Path::new(file_name);
+    let stem = source
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| file_name.to_string());
+    let extension = source
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+    (1..)
+        .map(|index| dir.join(format!("{} ({}){}", stem, index, extension)))
+        .find(|path| !path.exists())
+        .expect("unbounded index always finds a free name")
+}
+
+fn compressed_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(".gz");
+    PathBuf::from(name)
+}
+
+/// 跨文件系统时 rename 会失败，改为复制后删除源文件
+fn move_file(source: &Path, target: &Path) -> Result<(), String> {
+    if fs::rename(source, target).is_ok() {
+        return Ok(());
+    }
+    fs::copy(source, target).map_err(|e| format!("复制文件失败: {}", e))?;
+    if let Err(error) = fs::remove_file(source) {
+        let _ = fs::remove_file(target);
+        return Err(format!("删除源文件失败: {}", error));
+    }
+    Ok(())
+}
+
+/// 在目标旁新建临时文件：`name.gz.tmp`、`name.gz.1.tmp`……，不会截断已有的文件
+fn create_temp_file(target: &Path) -> Result<(PathBuf, File), String> {
+    for index in 0..1000 {
+        let mut temp_name = target.as_os_str().to_os_string();
+        if index > 0 {
+            temp_name.push(format!(".{}", index));
+        }
+        temp_name.push(".tmp");
+        let temp_path = PathBuf::from(temp_name);
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp_path)
+        {
+            Ok(file) => return Ok((temp_path, file)),
+            Err(error) if error.kind() == ErrorKind::AlreadyExists => continue,
+            Err(error) => return Err(format!("创建压缩文件失败: {}", error)),
+        }
+    }
+    Err("创建压缩文件失败: 找不到可用的临时文件名".into())
+}
+
+/// 压缩到临时文件，成功后再替换原文件；压缩后没有变小时保留原文件
+fn compress_file(source: &Path, target: &Path, original_size: u64) -> Result<Option<u64>, String> {
+    let (temp_path, output) = create_temp_file(target)?;
+
+    let result = (|| {
+        let input = File::open(source).map_err(|e| format!("打开文件失败: {}", e))?;
+        let mut encoder = GzEncoder::new(BufWriter::new(output), Compression::fast());
+        std::io::copy(&mut BufReader::new(input), &mut encoder)
+            .map_err(|e| format!("压缩失败: {}", e))?;
+        let mut writer = encoder.finish().map_err(|e| format!("压缩失败: {}", e))?;
+        writer
+            .flush()
+            .map_err(|e| format!("写入压缩文件失败: {}", e))?;
+        let output = writer
+            .into_inner()
+            .map_err(|e| format!("写入压缩文件失败: {}", e))?;
+        // 保留原文件的修改时间，便于之后按时间筛选
+        if let Ok(modified) = fs::metadata(source).and_then(|metadata| metadata.modified()) {
+            let _ = filetime::set_file_handle_times(
+                &output,
+                None,
+                Some(FileTime::from_system_time(modified)),
+            );
+        }
+        output
+            .metadata()
+            .map(|metadata| metadata.len())
+            .map_err(|e| format!("读取压缩文件信息失败: {}", e))
+    })();
+
+    let compressed_size = match result {
+        Ok(size) => size,
+        Err(error) => {
+            let _ = fs::remove_file(&temp_path);
+            return Err(error);
+        }
+    };
+    if compressed_size >= original_size {
+        let _ = fs::remove_file(&temp_path);
+        return Ok(None);
+    }
+
+    if let Err(error) = fs::rename(&temp_path, target) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(format!("保存压缩文件失败: {}", error));
+    }
+    if let Err(error) = fs::remove_file(source) {
+        let _ = fs::remove_file(target);
+        return Err(format!("删除原文件失败: {}", error));
+    }
+    Ok(Some(compressed_size))
+}
+
+fn apply_to_path(
+    path: &str,
+    action: CleanupAction,
+    destination: Option<&Path>,
+    dry_run: bool,
+) -> CleanupItemResult {
+    let mut item = CleanupItemResult {
+        path: path.to_string(),
+        target_path: None,
+        status: "failed".into(),
+        message: String::new(),
+        reclaimed_size: 0,
+    };
+    let source = Path::new(path);
+
+    // 只处理普通文件和符号链接本身，避免误删整个目录
+    let metadata = match fs::symlink_metadata(source) {
+        Ok(metadata) => metadata,
+        Err(error) => {
+            item.message = format!("无法访问文件: {}", error);
+            return item;
+        }
+    };
+    if metadata.is_dir() {
+        item.message = "只能处理文件，不能处理文件夹".into();
+        return item;
+    }
+    let size = if metadata.is_file() {
+        metadata.len()
+    } else {
+        0
+    };
+
+    let target = match action {
+        CleanupAction::Trash | CleanupAction::Delete => None,
+        CleanupAction::Move => {
+            let Some(destination) = destination else {
+                item.message = "未指定目标文件夹".into();
+                return item;
+            };
+            let file_name = source
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            Some(unique_target_path(destination, &file_name))
+        }
+        CleanupAction::Compress => {
+            if !metadata.is_file() {
+                item.message = "只能压缩普通文件".into();
+                return item;
+            }
+            let target = compressed_path(source);
+            if target.exists() {
+                item.message = format!("目标文件已存在: {}", target.display());
+                return item;
+            }
+            Some(target)
+        }
+    };
+    item.target_path = target.as_ref().map(|target| target.display().to_string());
+
+    if dry_run {
+        item.status = "planned".into();
+        item.reclaimed_size = if action == CleanupAction::Compress {
+            0
+        } else {
+            size
+        };
+        item.message = match (&action, &target) {
+            (CleanupAction::Trash, _) => "将移到回收站".into(),
+            (CleanupAction::Delete, _) => "将永久删除".into(),
+            (CleanupAction::Move, Some(target)) => format!("将移动到 {}", target.display()),
+            (CleanupAction::Compress, Some(target)) => format!("将压缩为 {}", target.display()),
+            _ => String::new(),
+        };
+        return item;
+    }
+
+    let outcome = match (action, target.as_deref()) {
+        (CleanupAction::Move, Some(target)) => move_file(source, target).map(|_| Some(size)),
+        (CleanupAction::Compress, Some(target)) => compress_file(source, target, size)
+            .map(|compressed| compressed.map(|compressed_size| size - compressed_size)),
+        _ => remove_file_path(source, action == CleanupAction::Trash).map(|_| Some(size)),
+    };
+
+    match outcome {
+        Ok(Some(reclaimed_size)) => {
+            item.status = "success".into();
+            item.message = "处理完成".into();
+            item.reclaimed_size = reclaimed_size;
+        }
+        Ok(None) => {
+            item.status = "skipped".into();
+            item.message = "压缩后没有变小，已保留原文件".into();
+            item.target_path = None;
+        }
+        Err(error) => {
+            warn!("[清理] 处理失败: {} ({})", path, error);
+            item.message = error;
+        }
+    }
+    item
+}
+
+fn apply_cleanup(
+    paths: &[String],
+    action: CleanupAction,
+    destination: Option<&str>,
+    dry_run: bool,
+) -> Result<CleanupResult, String> {
+    let destination = match (action, destination) {
+        (CleanupAction::Move, Some(destination)) => {
+            let destination = Path::new(destination);
+            if !destination.is_dir() {
+                return Err("目标文件夹不存在".into());
+            }
+            Some(destination)
+        }
+        (CleanupAction::Move, None) => return Err("移动文件需要指定目标文件夹".into()),
+        _ => None,
+    };
+
+    let items: Vec<CleanupItemResult> = paths
+        .iter()
+        .map(|path| apply_to_path(path, action, destination, dry_run))
+        .collect();
+
+    let count = |status: &str| items.iter().filter(|item| item.status == status).count() as u32;
+    Ok(CleanupResult {
+        action,
+        dry_run,
+        success_count: count(if dry_run { "planned" } else { "success" }),
+        skipped_count: count("skipped"),
+        failed_count: count("failed"),
+        reclaimed_size: items.iter().map(|item| item.reclaimed_size).sum(),
+        items,
+    })
+}
+
+/// 对扫描结果中的文件执行清理操作，`dry_run` 时只检查并返回将要发生的变化
+#[tauri::command]
+pub async fn apply_cleanup_action(
+    paths: Vec<String>,
+    action: CleanupAction,
+    destination: Option<String>,
+    dry_run: Option<bool>,
+) -> Result<CleanupResult, String> {
+    let dry_run = dry_run.unwrap_or(false);
+    info!(
+        "[清理] {:?} {} 个文件, 预演: {}",
+        action,
+        paths.len(),
+        dry_run
+    );
+
+    let result = tokio::task::spawn_blocking(move || {
+        apply_cleanup(&paths, action, destination.as_deref(), dry_run)
+    })
+    .await
+    .map_err(|e| format!("任务执行失败: {}", e))??;
+
+    info!(
+        "[清理] 完成: 成功 {} 个, 跳过 {} 个, 失败 {} 个, 释放 {} 字节",
+        result.success_count, result.skipped_count, result.failed_count, result.reclaimed_size
+    );
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    static NEXT_TEST_ID: AtomicUsize = AtomicUsize::new(0);
+
+    struct TestDir {
+        path: PathBuf,
+    }
+
+    impl TestDir {
+        fn new() -> Self {
+            let unique = format!(
+                "file-stats-cleanup-test-{}-{}-{}",
+                std::process::id(),
+                NEXT_TEST_ID.fetch_add(1, Ordering::Relaxed),
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_nanos()
+            );
+            let path = std::env::temp_dir().join(unique);
+            fs::create_dir_all(&path).expect("failed to create temp test directory");
+            Self { path }
+        }
+
+        fn path(&self) -> &Path {
+            &self.path
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+
+    fn path_string(path: &Path) -> String {
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn dry_run_reports_changes_without_touching_files() {
+        let temp_dir = TestDir::new();
+        let big = temp_dir.path().join("big.log");
+        fs::write(&big, vec![b'a'; 4096]).expect("failed to write test file");
+
+        let result = apply_cleanup(
+            &[
+                path_string(&big),
+                path_string(&temp_dir.path().join("missing.log")),
+                path_string(temp_dir.path()),
+            ],
+            CleanupAction::Delete,
+            None,
+            true,
+        )
+        .expect("dry run should succeed");
+
+        assert!(result.dry_run);
+        assert_eq!(result.success_count, 1);
+        assert_eq!(result.failed_count, 2);
+        assert_eq!(result.reclaimed_size, 4096);
+        assert_eq!(result.items[0].status, "planned");
+        assert!(big.exists());
+    }
+
+    #[test]
+    fn delete_and_move_report_per_file_results() {
+        let temp_dir = TestDir::new();
+        let source_dir = temp_dir.path().join("source");
+        let archive_dir = temp_dir.path().join("archive");
+        fs::create_dir_all(&source_dir).expect("failed to create source dir");
+        fs::create_dir_all(&archive_dir).expect("failed to create archive dir");
+        let old = source_dir.join("old.bin");
+        let report = source_dir.join("report.pdf");
+        fs::write(&old, b"old").expect("failed to write test file");
+        fs::write(&report, b"new").expect("failed to write test file");
+        fs::write(archive_dir.join("report.pdf"), b"existing").expect("failed to write");
+
+        let deleted = apply_cleanup(&[path_string(&old)], CleanupAction::Delete, None, false)
+            .expect("delete should succeed");
+        assert_eq!(deleted.success_count, 1);
+        assert!(!old.exists());
+
+        let moved = apply_cleanup(
+            &[path_string(&report)],
+            CleanupAction::Move,
+            Some(&path_string(&archive_dir)),
+            false,
+        )
+        .expect("move should succeed");
+        assert_eq!(moved.success_count, 1);
+        assert!(!report.exists());
+        let target = archive_dir.join("report (1).pdf");
+        assert_eq!(moved.items[0].target_path, Some(path_string(&target)));
+        assert_eq!(fs::read(&target).expect("moved file"), b"new");
+
+        let missing_destination =
+            apply_cleanup(&[path_string(&report)], CleanupAction::Move, None, false);
+        assert!(missing_destination.is_err());
+    }
+
+    #[test]
+    fn compress_replaces_file_with_smaller_gzip_only() {
+        let temp_dir = TestDir::new();
+        let log = temp_dir.path().join("app.log");
+        let content = "2024-05-01 INFO request handled\n".repeat(2048);
+        fs::write(&log, &content).expect("failed to write log file");
+        let tiny = temp_dir.path().join("tiny.txt");
+        fs::write(&tiny, b"x").expect("failed to write tiny file");
+        // 与临时文件同名的已有文件不能被截断
+        let unrelated = temp_dir.path().join("app.log.gz.tmp");
+        fs::write(&unrelated, b"keep").expect("failed to write unrelated file");
+
+        let result = apply_cleanup(
+            &[path_string(&log), path_string(&tiny)],
+            CleanupAction::Compress,
+            None,
+            false,
+        )
+        .expect("compress should succeed");
+
+        assert_eq!(result.success_count, 1);
+        assert_eq!(result.skipped_count, 1);
+        assert!(!log.exists());
+        assert!(tiny.exists());
+        assert!(!temp_dir.path().join("tiny.txt.gz").exists());
+        assert_eq!(fs::read(&unrelated).expect("unrelated file"), b"keep");
+
+        let gz_path = temp_dir.path().join("app.log.gz");
+        let mut decoded = String::new();
+        flate2::read::GzDecoder::new(File::open(&gz_path).expect("gzip file should exist"))
+            .read_to_string(&mut decoded)
+            .expect("gzip should decode");
+        assert_eq!(decoded, content);
+        assert_eq!(
+            result.reclaimed_size,
+            content.len() as u64 - fs::metadata(&gz_path).expect("gzip metadata").len()
+        );
+    }
+}
@@ -0,0 +1,169 @@
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// 每种类型最多抽样的文件数
+const MAX_SAMPLE_FILES: usize = 8;
+/// 每个文件最多读取的数据块数，块在文件内均匀分布
+const MAX_BLOCKS_PER_FILE: u64 = 4;
+const BLOCK_SIZE: u64 = 32 * 1024;
+
+/// 按抽样数据估算的压缩效果（LZ4，接近常见归档工具的快速档）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompressionEstimate {
+    pub sampled_files: u64,
+    pub sampled_bytes: u64,
+    pub compressed_bytes: u64,
+    /// 压缩后 / 压缩前，不会超过 1（无法压缩的数据按原样存储）
+    pub ratio: f64,
+    pub estimated_compressed_size: u64,
+    pub projected_savings: u64,
+}
+
+/// 某种类型的抽样候选：保留路径哈希最小的若干个文件
+///
+/// 按哈希挑选与遍历顺序无关，子树并行合并后结果仍然确定。
+#[derive(Debug, Default)]
+pub struct CompressionCandidates {
+    entries: Vec<(u64, PathBuf, u64)>,
+}
+
+impl CompressionCandidates {
+    pub fn offer(&mut self, path: &Path, size: u64) {
+        if size == 0 {
+            return;
+        }
+        let hash = xxhash_rust::xxh3::xxh3_64(path.as_os_str().to_string_lossy().as_bytes());
+        if self.entries.len() >= MAX_SAMPLE_FILES
+            && self
+                .entries
+                .last()
+                .is_some_and(|(last, _, _)| hash >= *last)
+        {
+            return;
+        }
+        let index = self
+            .entries
+            .partition_point(|(existing, _, _)| *existing < hash);
+        self.entries.insert(index, (hash, path.to_path_buf(), size));
+        self.entries.truncate(MAX_SAMPLE_FILES);
+    }
+
+    pub fn merge(&mut self, other: CompressionCandidates) {
+        for (_, path, size) in other.entries {
+            self.offer(&path, size);
+        }
+    }
+
+    /// 读取抽样数据并压缩；读不到任何数据或被取消时返回 None
+    pub fn estimate(&self, total_size: u64, cancelled: &AtomicBool) -> Option<CompressionEstimate> {
+        let mut sampled_files = 0;
+        let mut sampled_bytes = 0;
+        let mut compressed_bytes = 0;
+
+        for (_, path, size) in &self.entries {
+            if cancelled.load(Ordering::Relaxed) {
+                return None;
+            }
+            let Ok(blocks) = read_sample_blocks(path, *size) else {
+                continue;
+            };
+            if blocks.is_empty() {
+                continue;
+            }
+            sampled_files += 1;
+            for block in blocks {
+                sampled_bytes += block.len() as u64;
+                compressed_bytes += lz4_flex::block::compress(&block).len() as u64;
+            }
+        }
+
+        if sampled_bytes == 0 {
+            return None;
+        }
+        let compressed_bytes = compressed_bytes.min(sampled_bytes);
+        let ratio = compressed_bytes as f64 / sampled_bytes as f64;
+        let estimated_compressed_size = (total_size as f64 * ratio).round() as u64;
+        Some(CompressionEstimate {
+            sampled_files,
+            sampled_bytes,
+            compressed_bytes,
+            ratio,
+            estimated_compressed_size,
+            projected_savings: total_size.saturating_sub(estimated_compressed_size),
+        })
+    }
+}
+
+/// 小文件整体读取，大文件读取首尾及中间均匀分布的几个块
+fn read_sample_blocks(path: &Path, size: u64) -> std::io::Result<Vec<Vec<u8>>> {
+    let mut file = File::open(path)?;
+    // u64::div_ceil 需要 Rust 1.73，这里手动向上取整以兼容 1.70
+    #[allow(clippy::manual_div_ceil)]
+    let block_count = ((size + BLOCK_SIZE - 1) / BLOCK_SIZE).min(MAX_BLOCKS_PER_FILE);
+    let mut blocks = Vec::with_capacity(block_count as usize);
+
+    for index in 0..block_count {
+        let offset = if size <= BLOCK_SIZE * MAX_BLOCKS_PER_FILE {
+            index * BLOCK_SIZE
+        } else {
+            index * (size - BLOCK_SIZE) / (block_count - 1)
+        };
+        file.seek(SeekFrom::Start(offset))?;
+        let mut block = Vec::with_capacity(BLOCK_SIZE as usize);
+        (&mut file).take(BLOCK_SIZE).read_to_end(&mut block)?;
+        if block.is_empty() {
+            break;
+        }
+        blocks.push(block);
+    }
+    Ok(blocks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn candidates_keep_smallest_hashes_regardless_of_order() {
+        let paths: Vec<PathBuf> = (0..40)
+            .map(|index| PathBuf::from(format!("/data/file-{}.log", index)))
+            .collect();
+
+        let mut forward = CompressionCandidates::default();
+        for path in &paths {
+            forward.offer(path, 10);
+        }
+
+        let mut left = CompressionCandidates::default();
+        let mut right = CompressionCandidates::default();
+        for path in paths.iter().rev() {
+            if path.to_string_lossy().len() % 2 == 0 {
+                left.offer(path, 10);
+            } else {
+                right.offer(path, 10);
+            }
+        }
+        left.merge(right);
+
+        let selected = |candidates: &CompressionCandidates| {
+            candidates
+                .entries
+                .iter()
+                .map(|(_, path, _)| path.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(forward.entries.len(), MAX_SAMPLE_FILES);
+        assert_eq!(selected(&forward), selected(&left));
+    }
+
+    #[test]
+    fn candidates_ignore_empty_files() {
+        let mut candidates = CompressionCandidates::default();
+        candidates.offer(Path::new("/data/empty.txt"), 0);
+        assert!(candidates.entries.is_empty());
+        assert!(candidates.estimate(0, &AtomicBool::new(false)).is_none());
+    }
+}
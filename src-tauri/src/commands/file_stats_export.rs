@@ -0,0 +1,483 @@
+use chrono::Local;
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::path::Path;
+
+use super::file_stats::ScanResult;
+
+const CHART_MAX_BARS: usize = 10;
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportFormat {
+    Csv,
+    Json,
+    Html,
+}
+
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    exported_at: String,
+    #[serde(flatten)]
+    result: &'a ScanResult,
+}
+
+fn format_size(bytes: u64) -> String {
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+
+    let units = ["KB", "MB", "GB", "TB", "PB"];
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit_index = 0;
+    while value >= 1024.0 && unit_index < units.len() - 1 {
+        value /= 1024.0;
+        unit_index += 1;
+    }
+
+    let digits = if value >= 100.0 {
+        0
+    } else if value >= 10.0 {
+        1
+    } else {
+        2
+    };
+    format!("{:.*} {}", digits, value, units[unit_index])
+}
+
+fn percent(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 / total as f64 * 100.0
+    }
+}
+
+fn escape_csv(value: &str) -> String {
+    // 文件名和路径不受我们控制，以这些字符开头时表格软件会当作公式执行
+    let value = if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\n', '\r']) || value.trim() != value {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+/// 报告头部的概要字段，三种格式共用
+fn summary_rows(result: &ScanResult, exported_at: &str) -> Vec<(&'static str, String)> {
+    vec![
+        ("扫描目录", result.root_path.clone()),
+        ("扫描时间", result.scanned_at.clone()),
+        ("导出时间", exported_at.to_string()),
+        ("文件总数", result.total_files.to_string()),
+        ("目录总数", result.folder_count.to_string()),
+        ("逻辑大小 (字节)", result.total_size.to_string()),
+        ("占用空间 (字节)", result.total_allocated_size.to_string()),
+        ("文件类型数", result.type_count.to_string()),
+        ("稀疏文件数", result.sparse_files.to_string()),
+        ("跳过项目数", result.skipped_files.to_string()),
+        ("权限不足数", result.permission_denied_files.to_string()),
+        ("排除规则", result.options.exclude_globs.join("; ")),
+        (
+            "最大深度",
+            result
+                .options
+                .max_depth
+                .map(|depth| depth.to_string())
+                .unwrap_or_else(|| "不限".into()),
+        ),
+        ("跳过隐藏项", yes_no(result.options.skip_hidden)),
+        ("不跨文件系统", yes_no(result.options.one_filesystem)),
+        ("跟随符号链接", yes_no(result.options.follow_symlinks)),
+        ("估算压缩率", yes_no(result.options.estimate_compression)),
+        ("按规则排除数", result.excluded.by_glob.to_string()),
+        ("隐藏项排除数", result.excluded.hidden.to_string()),
+        ("超出深度目录数", result.excluded.by_depth.to_string()),
+        (
+            "其他文件系统目录数",
+            result.excluded.other_filesystem.to_string(),
+        ),
+        ("符号链接循环数", result.excluded.symlink_loops.to_string()),
+        (
+            "问题路径数",
+            result
+                .health
+                .as_ref()
+                .map(|health| health.total().to_string())
+                .unwrap_or_else(|| "未检查".into()),
+        ),
+        (
+            "多版本文件簇数",
+            result
+                .versions
+                .as_ref()
+                .map(|versions| versions.cluster_count.to_string())
+                .unwrap_or_else(|| "未检查".into()),
+        ),
+        (
+            "多版本可节省 (字节)",
+            result
+                .versions
+                .as_ref()
+                .map(|versions| versions.reclaimable_size.to_string())
+                .unwrap_or_else(|| "未检查".into()),
+        ),
+    ]
+}
+
+fn yes_no(value: bool) -> String {
+    if value { "是" } else { "否" }.to_string()
+}
+
+fn build_csv_report(result: &ScanResult, exported_at: &str) -> String {
+    // 带 BOM，方便 Excel 正确识别中文
+    let mut csv = String::from("\u{feff}");
+
+    csv.push_str("字段,值\n");
+    for (label, value) in summary_rows(result, exported_at) {
+        let _ = writeln!(csv, "{},{}", escape_csv(label), escape_csv(&value));
+    }
+
+    csv.push_str(
+        "\n扩展名,文件数,逻辑大小 (字节),占用空间 (字节),稀疏文件数,数量占比 (%),大小占比 (%),\
+         预计压缩率 (%),预计可节省 (字节)\n",
+    );
+    for item in &result.stats {
+        // 未估算的类型留空
+        let (ratio, savings) = item
+            .compression
+            .as_ref()
+            .map(|estimate| {
+                (
+                    format!("{:.2}", estimate.ratio * 100.0),
+                    estimate.projected_savings.to_string(),
+                )
+            })
+            .unwrap_or_default();
+        let _ = writeln!(
+            csv,
+            "{},{},{},{},{},{:.2},{:.2},{},{}",
+            escape_csv(&item.extension),
+            item.count,
+            item.total_size,
+            item.allocated_size,
+            item.sparse_count,
+            percent(item.count, result.total_files),
+            percent(item.total_size, result.total_size),
+            ratio,
+            savings,
+        );
+    }
+
+    if !result.sample_errors.is_empty() {
+        csv.push_str("\n问题路径,原因\n");
+        for issue in &result.sample_errors {
+            let _ = writeln!(
+                csv,
+                "{},{}",
+                escape_csv(&issue.path),
+                escape_csv(&issue.reason)
+            );
+        }
+    }
+
+    csv
+}
+
+fn build_json_report(result: &ScanResult, exported_at: &str) -> Result<String, String> {
+    serde_json::to_string_pretty(&JsonReport {
+        exported_at: exported_at.to_string(),
+        result,
+    })
+    .map_err(|error| format!("生成 JSON 报告失败: {}", error))
+}
+
+/// 按逻辑大小排名前几的类型画横向条形图（内联 SVG，无外部依赖）
+fn build_size_chart(result: &ScanResult) -> String {
+    let bars: Vec<_> = result.stats.iter().take(CHART_MAX_BARS).collect();
+    if bars.is_empty() {
+        return String::new();
+    }
+
+    let max_size = bars
+        .iter()
+        .map(|item| item.total_size)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let row_height = 28;
+    let label_width = 120;
+    let bar_area = 480;
+    let height = bars.len() * row_height + 8;
+
+    let mut svg = format!(
+        "<svg class=\"chart\" viewBox=\"0 0 {} {}\" role=\"img\" aria-label=\"文件类型大小分布\">",
+        label_width + bar_area + 120,
+        height
+    );
+    for (index, item) in bars.iter().enumerate() {
+        let y = index * row_height + 4;
+        let width = ((item.total_size as f64 / max_size as f64) * bar_area as f64).max(1.0);
+        let _ = write!(
+            svg,
+            "<text x=\"{}\" y=\"{}\" text-anchor=\"end\">{}</text>\
+             <rect x=\"{}\" y=\"{}\" width=\"{:.1}\" height=\"{}\" rx=\"4\"></rect>\
+             <text x=\"{:.1}\" y=\"{}\">{}</text>",
+            label_width - 8,
+            y + 17,
+            escape_html(&item.extension),
+            label_width,
+            y + 4,
+            width,
+            row_height - 10,
+            label_width as f64 + width + 6.0,
+            y + 17,
+            escape_html(&format_size(item.total_size)),
+        );
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+fn build_html_report(result: &ScanResult, exported_at: &str) -> String {
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"zh-CN\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>文件统计报告 - {}</title>\n<style>\n\
+         body {{ font-family: -apple-system, \"Segoe UI\", \"PingFang SC\", \"Microsoft YaHei\", sans-serif; margin: 32px; color: #0f172a; }}\n\
+         h1 {{ font-size: 22px; }} h2 {{ font-size: 17px; margin-top: 32px; }}\n\
+         table {{ border-collapse: collapse; width: 100%; font-size: 13px; }}\n\
+         th, td {{ border-bottom: 1px solid #e2e8f0; padding: 6px 10px; text-align: left; }}\n\
+         th {{ background: #f8fafc; }} td.num {{ text-align: right; font-variant-numeric: tabular-nums; }}\n\
+         .chart {{ width: 100%; max-width: 760px; font-size: 12px; }} .chart rect {{ fill: #3b82f6; }}\n\
+         .chart text {{ fill: #334155; }}\n\
+         </style>\n</head>\n<body>\n<h1>文件统计报告</h1>\n",
+        escape_html(&result.root_path)
+    );
+
+    html.push_str("<h2>概览</h2>\n<table>\n");
+    for (label, value) in summary_rows(result, exported_at) {
+        let _ = writeln!(
+            html,
+            "<tr><th>{}</th><td>{}</td></tr>",
+            escape_html(label),
+            escape_html(&value)
+        );
+    }
+    let _ = writeln!(
+        html,
+        "<tr><th>逻辑大小</th><td>{}</td></tr>\n<tr><th>占用空间</th><td>{}</td></tr>",
+        format_size(result.total_size),
+        format_size(result.total_allocated_size)
+    );
+    html.push_str("</table>\n");
+
+    html.push_str("<h2>大小分布</h2>\n");
+    html.push_str(&build_size_chart(result));
+
+    html.push_str(
+        "\n<h2>文件类型</h2>\n<table>\n<tr><th>扩展名</th><th>文件数</th><th>数量占比</th>\
+         <th>逻辑大小</th><th>占用空间</th><th>大小占比</th><th>稀疏文件</th>",
+    );
+    let show_compression = result.options.estimate_compression;
+    if show_compression {
+        html.push_str("<th>预计压缩率</th><th>预计可节省</th>");
+    }
+    html.push_str("</tr>\n");
+    for item in &result.stats {
+        let compression_cells = if !show_compression {
+            String::new()
+        } else if let Some(estimate) = &item.compression {
+            format!(
+                "<td class=\"num\">{:.1}%</td><td class=\"num\">{}</td>",
+                estimate.ratio * 100.0,
+                format_size(estimate.projected_savings)
+            )
+        } else {
+            "<td class=\"num\">-</td><td class=\"num\">-</td>".to_string()
+        };
+        let _ = writeln!(
+            html,
+            "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{:.1}%</td>\
+             <td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{:.1}%</td>\
+             <td class=\"num\">{}</td>{}</tr>",
+            escape_html(&item.extension),
+            item.count,
+            percent(item.count, result.total_files),
+            format_size(item.total_size),
+            format_size(item.allocated_size),
+            percent(item.total_size, result.total_size),
+            item.sparse_count,
+            compression_cells,
+        );
+    }
+    html.push_str("</table>\n");
+
+    if !result.sample_errors.is_empty() {
+        html.push_str("<h2>问题样本</h2>\n<table>\n<tr><th>路径</th><th>原因</th></tr>\n");
+        for issue in &result.sample_errors {
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td></tr>",
+                escape_html(&issue.path),
+                escape_html(&issue.reason)
+            );
+        }
+        html.push_str("</table>\n");
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn build_report(result: &ScanResult, format: ReportFormat) -> Result<String, String> {
+    let exported_at = Local::now().to_rfc3339();
+    match format {
+        ReportFormat::Csv => Ok(build_csv_report(result, &exported_at)),
+        ReportFormat::Json => build_json_report(result, &exported_at),
+        ReportFormat::Html => Ok(build_html_report(result, &exported_at)),
+    }
+}
+
+/// 导出文件统计报告（CSV / JSON / HTML）
+#[tauri::command]
+pub fn export_file_stats(
+    result: ScanResult,
+    format: ReportFormat,
+    output: String,
+) -> Result<String, String> {
+    let content = build_report(&result, format)?;
+
+    if let Some(parent) = Path::new(&output).parent() {
+        if !parent.as_os_str().is_empty() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("创建导出目录失败: {}", e))?;
+        }
+    }
+    std::fs::write(&output, content).map_err(|e| format!("写入报告失败: {}", e))?;
+
+    info!("[文件统计] 已导出 {:?} 报告: {}", format, output);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::file_stats::{FileStats, ScanExclusions, ScanIssue, ScanOptions};
+
+    fn sample_result() -> ScanResult {
+        ScanResult {
+            root_path: "/data/share".into(),
+            scanned_at: "2024-05-01T10:00:00+08:00".into(),
+            stats: vec![
+                FileStats {
+                    extension: ".mp4".into(),
+                    count: 2,
+                    total_size: 3 * 1024 * 1024,
+                    allocated_size: 3 * 1024 * 1024,
+                    sparse_count: 0,
+                    compression: None,
+                },
+                FileStats {
+                    extension: "(无扩展名)".into(),
+                    count: 2,
+                    total_size: 1024,
+                    allocated_size: 8192,
+                    sparse_count: 0,
+                    compression: None,
+                },
+            ],
+            total_files: 4,
+            folder_count: 1,
+            total_size: 3 * 1024 * 1024 + 1024,
+            total_allocated_size: 3 * 1024 * 1024 + 8192,
+            type_count: 2,
+            sparse_files: 0,
+            sparse_samples: Vec::new(),
+            skipped_files: 1,
+            permission_denied_files: 1,
+            excluded: ScanExclusions {
+                by_glob: 3,
+                ..Default::default()
+            },
+            ownership: None,
+            health: None,
+            versions: None,
+            options: ScanOptions {
+                exclude_globs: vec!["node_modules".into(), "*.tmp".into()],
+                max_depth: Some(4),
+                ..Default::default()
+            },
+            sample_errors: vec![ScanIssue {
+                path: "/data/share/a,<b>.txt".into(),
+                reason: "Permission denied".into(),
+            }],
+        }
+    }
+
+    #[test]
+    fn csv_report_includes_summary_stats_and_escaped_errors() {
+        let csv = build_csv_report(&sample_result(), "2024-05-01T11:00:00+08:00");
+
+        assert!(csv.starts_with('\u{feff}'));
+        assert!(csv.contains("扫描目录,/data/share\n"));
+        assert!(csv.contains("权限不足数,1\n"));
+        assert!(csv.contains("排除规则,node_modules; *.tmp\n"));
+        assert!(csv.contains("最大深度,4\n"));
+        assert!(csv.contains("按规则排除数,3\n"));
+        assert!(csv.contains(".mp4,2,3145728,3145728,0,50.00,99.97,,\n"));
+        assert!(csv.contains("\"/data/share/a,<b>.txt\",Permission denied\n"));
+
+        assert_eq!(escape_csv("=1+1"), "'=1+1");
+        assert_eq!(escape_csv("@SUM(A1)"), "'@SUM(A1)");
+        assert_eq!(
+            escape_csv("-2,HYPERLINK(\"x\")"),
+            "\"'-2,HYPERLINK(\"\"x\"\")\""
+        );
+        assert_eq!(escape_csv("a-b"), "a-b");
+    }
+
+    #[test]
+    fn json_report_round_trips_scan_result() {
+        let json = build_json_report(&sample_result(), "2024-05-01T11:00:00+08:00")
+            .expect("json report should build");
+        let value: serde_json::Value = serde_json::from_str(&json).expect("report should be json");
+
+        assert_eq!(value["exported_at"], "2024-05-01T11:00:00+08:00");
+        assert_eq!(value["root_path"], "/data/share");
+        assert_eq!(value["stats"][0]["extension"], ".mp4");
+        assert_eq!(value["skipped_files"], 1);
+        assert_eq!(value["options"]["max_depth"], 4);
+        assert_eq!(value["excluded"]["by_glob"], 3);
+    }
+
+    #[test]
+    fn html_report_is_escaped_and_contains_chart() {
+        let html = build_html_report(&sample_result(), "2024-05-01T11:00:00+08:00");
+
+        assert!(html.contains("<svg class=\"chart\""));
+        assert!(html.contains("3.00 MB"));
+        assert!(html.contains("/data/share/a,&lt;b&gt;.txt"));
+        assert!(!html.contains("<b>"));
+    }
+}
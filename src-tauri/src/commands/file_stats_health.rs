@@ -0,0 +1,147 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+use super::file_stats::FlaggedPaths;
+
+/// Windows 的 MAX_PATH，超过后很多同步工具和旧程序无法处理
+const LONG_PATH_LIMIT: usize = 260;
+/// 每个类别最多列出的路径数量
+const MAX_FLAGGED_PATHS: usize = 1000;
+const WINDOWS_INVALID_CHARS: [char; 9] = ['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+const WINDOWS_RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// 复制到其他系统前需要处理的问题路径，按类别给出完整计数
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HealthReport {
+    pub broken_symlinks: FlaggedPaths,
+    /// 完整路径超过 260 个字符（按 UTF-16 计）
+    pub long_paths: FlaggedPaths,
+    /// 包含 Windows 不允许的字符、控制字符，或使用 CON、NUL 等保留名
+    pub invalid_names: FlaggedPaths,
+    /// 以空格或 . 结尾，Windows 会静默去掉
+    pub trailing_spaces: FlaggedPaths,
+    /// 同一目录下仅大小写不同的名字，列出冲突中的每一项
+    pub case_collisions: FlaggedPaths,
+    /// 不是合法 UTF-8 的名字，界面上显示的是替换字符后的结果
+    pub non_utf8_names: FlaggedPaths,
+}
+
+impl Default for HealthReport {
+    fn default() -> Self {
+        Self {
+            broken_symlinks: FlaggedPaths::new(MAX_FLAGGED_PATHS),
+            long_paths: FlaggedPaths::new(MAX_FLAGGED_PATHS),
+            invalid_names: FlaggedPaths::new(MAX_FLAGGED_PATHS),
+            trailing_spaces: FlaggedPaths::new(MAX_FLAGGED_PATHS),
+            case_collisions: FlaggedPaths::new(MAX_FLAGGED_PATHS),
+            non_utf8_names: FlaggedPaths::new(MAX_FLAGGED_PATHS),
+        }
+    }
+}
+
+impl HealthReport {
+    pub fn total(&self) -> u64 {
+        self.broken_symlinks.count
+            + self.long_paths.count
+            + self.invalid_names.count
+            + self.trailing_spaces.count
+            + self.case_collisions.count
+            + self.non_utf8_names.count
+    }
+
+    /// 检查路径和文件名本身，不需要额外的系统调用
+    pub fn check_name(&mut self, path: &Path, name: &OsStr) {
+        if path.as_os_str().to_string_lossy().encode_utf16().count() > LONG_PATH_LIMIT {
+            self.long_paths.push(path);
+        }
+
+        let Some(name) = name.to_str() else {
+            self.non_utf8_names.push(path);
+            return;
+        };
+        if has_windows_invalid_name(name) {
+            self.invalid_names.push(path);
+        }
+        if name.ends_with(' ') || name.ends_with('.') {
+            self.trailing_spaces.push(path);
+        }
+    }
+
+    pub fn record_broken_symlink(&mut self, path: &Path) {
+        self.broken_symlinks.push(path);
+    }
+
+    /// `folded_names` 为同一目录下按小写归并的名字
+    pub fn check_case_collisions(&mut self, folded_names: HashMap<String, Vec<PathBuf>>) {
+        for paths in folded_names.into_values() {
+            if paths.len() < 2 {
+                continue;
+            }
+            for path in paths {
+                self.case_collisions.push(&path);
+            }
+        }
+    }
+
+    pub fn merge(&mut self, other: HealthReport) {
+        self.broken_symlinks.merge(other.broken_symlinks);
+        self.long_paths.merge(other.long_paths);
+        self.invalid_names.merge(other.invalid_names);
+        self.trailing_spaces.merge(other.trailing_spaces);
+        self.case_collisions.merge(other.case_collisions);
+        self.non_utf8_names.merge(other.non_utf8_names);
+    }
+}
+
+fn has_windows_invalid_name(name: &str) -> bool {
+    if name
+        .chars()
+        .any(|ch| ch.is_control() || WINDOWS_INVALID_CHARS.contains(&ch))
+    {
+        return true;
+    }
+
+    // 保留名带扩展名同样无效，例如 nul.txt
+    let stem = name.split('.').next().unwrap_or(name).trim_end();
+    WINDOWS_RESERVED_NAMES
+        .iter()
+        .any(|reserved| stem.eq_ignore_ascii_case(reserved))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_invalid_names_cover_characters_and_reserved_names() {
+        assert!(has_windows_invalid_name("report:final.docx"));
+        assert!(has_windows_invalid_name("a?b"));
+        assert!(has_windows_invalid_name("tab\there"));
+        assert!(has_windows_invalid_name("CON"));
+        assert!(has_windows_invalid_name("nul.txt"));
+        assert!(has_windows_invalid_name("Com1.log"));
+        assert!(!has_windows_invalid_name("console.txt"));
+        assert!(!has_windows_invalid_name("COM10"));
+        assert!(!has_windows_invalid_name("照片 2024.jpg"));
+    }
+
+    #[test]
+    fn check_name_flags_long_paths_and_trailing_spaces() {
+        let mut report = HealthReport::default();
+        let long_path = PathBuf::from(format!("/data/{}", "a".repeat(300)));
+        report.check_name(&long_path, OsStr::new("a"));
+        report.check_name(Path::new("/data/notes "), OsStr::new("notes "));
+        report.check_name(Path::new("/data/notes."), OsStr::new("notes."));
+        report.check_name(Path::new("/data/ok.txt"), OsStr::new("ok.txt"));
+
+        assert_eq!(report.long_paths.count, 1);
+        assert_eq!(report.trailing_spaces.count, 2);
+        assert_eq!(report.invalid_names.count, 0);
+        assert_eq!(report.total(), 3);
+    }
+}
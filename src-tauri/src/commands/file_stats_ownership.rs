@@ -0,0 +1,219 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::Metadata;
+use std::path::Path;
+
+use super::file_stats::FlaggedPaths;
+
+const MAX_FLAGGED_SAMPLES: usize = 100;
+
+/// 按属主或属组汇总的空间占用
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OwnerUsage {
+    pub id: u32,
+    /// 无法解析（例如已删除的账户）时为空
+    pub name: Option<String>,
+    pub count: u64,
+    pub total_size: u64,
+    pub allocated_size: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OwnershipReport {
+    pub by_user: Vec<OwnerUsage>,
+    pub by_group: Vec<OwnerUsage>,
+    pub world_writable: FlaggedPaths,
+    /// 设置了 setuid 或 setgid 位的文件
+    pub setuid: FlaggedPaths,
+    /// 当前用户无法读取的文件
+    pub unreadable: FlaggedPaths,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct UsageTotals {
+    count: u64,
+    size: u64,
+    allocated_size: u64,
+}
+
+impl UsageTotals {
+    fn add(&mut self, other: UsageTotals) {
+        self.count += other.count;
+        self.size += other.size;
+        self.allocated_size += other.allocated_size;
+    }
+}
+
+/// 扫描过程中按子树累计的属主和权限信息
+#[derive(Debug)]
+pub struct OwnershipTotals {
+    by_user: HashMap<u32, UsageTotals>,
+    by_group: HashMap<u32, UsageTotals>,
+    world_writable: FlaggedPaths,
+    setuid: FlaggedPaths,
+    unreadable: FlaggedPaths,
+}
+
+impl Default for OwnershipTotals {
+    fn default() -> Self {
+        Self {
+            by_user: HashMap::new(),
+            by_group: HashMap::new(),
+            world_writable: FlaggedPaths::new(MAX_FLAGGED_SAMPLES),
+            setuid: FlaggedPaths::new(MAX_FLAGGED_SAMPLES),
+            unreadable: FlaggedPaths::new(MAX_FLAGGED_SAMPLES),
+        }
+    }
+}
+
+impl OwnershipTotals {
+    #[cfg(unix)]
+    pub fn record(&mut self, path: &Path, metadata: &Metadata, allocated_size: u64) {
+        use std::os::unix::fs::MetadataExt;
+
+        let usage = UsageTotals {
+            count: 1,
+            size: metadata.len(),
+            allocated_size,
+        };
+        self.by_user.entry(metadata.uid()).or_default().add(usage);
+        self.by_group.entry(metadata.gid()).or_default().add(usage);
+
+        let mode = metadata.mode();
+        if mode & 0o002 != 0 {
+            self.world_writable.push(path);
+        }
+        if mode & 0o6000 != 0 {
+            self.setuid.push(path);
+        }
+        if !is_readable(path) {
+            self.unreadable.push(path);
+        }
+    }
+
+    #[cfg(not(unix))]
+    pub fn record(&mut self, _path: &Path, _metadata: &Metadata, _allocated_size: u64) {}
+
+    pub fn merge(&mut self, other: OwnershipTotals) {
+        for (uid, usage) in other.by_user {
+            self.by_user.entry(uid).or_default().add(usage);
+        }
+        for (gid, usage) in other.by_group {
+            self.by_group.entry(gid).or_default().add(usage);
+        }
+        self.world_writable.merge(other.world_writable);
+        self.setuid.merge(other.setuid);
+        self.unreadable.merge(other.unreadable);
+    }
+
+    pub fn into_report(self) -> OwnershipReport {
+        OwnershipReport {
+            by_user: build_usage_list(self.by_user, resolve_user_name),
+            by_group: build_usage_list(self.by_group, resolve_group_name),
+            world_writable: self.world_writable,
+            setuid: self.setuid,
+            unreadable: self.unreadable,
+        }
+    }
+}
+
+fn build_usage_list(
+    totals: HashMap<u32, UsageTotals>,
+    resolve_name: fn(u32) -> Option<String>,
+) -> Vec<OwnerUsage> {
+    let mut list: Vec<OwnerUsage> = totals
+        .into_iter()
+        .map(|(id, usage)| OwnerUsage {
+            id,
+            name: resolve_name(id),
+            count: usage.count,
+            total_size: usage.size,
+            allocated_size: usage.allocated_size,
+        })
+        .collect();
+    list.sort_by(|a, b| {
+        b.total_size
+            .cmp(&a.total_size)
+            .then_with(|| b.count.cmp(&a.count))
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    list
+}
+
+/// 使用 access(2) 判断，能正确处理 root、附加组和 ACL
+#[cfg(unix)]
+fn is_readable(path: &Path) -> bool {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let Ok(c_path) = CString::new(path.as_os_str().as_bytes()) else {
+        return true;
+    };
+    unsafe { libc::access(c_path.as_ptr(), libc::R_OK) == 0 }
+}
+
+/// getpwuid_r / getgrgid_r 的缓冲区不足时返回 ERANGE，需要扩大后重试
+#[cfg(unix)]
+fn lookup_name<T>(
+    lookup: impl Fn(*mut T, *mut libc::c_char, usize, *mut *mut T) -> libc::c_int,
+    name_of: impl Fn(&T) -> *const libc::c_char,
+) -> Option<String> {
+    let mut buffer_len = 1024;
+    while buffer_len <= 1024 * 1024 {
+        let mut buffer = vec![0 as libc::c_char; buffer_len];
+        let mut entry = std::mem::MaybeUninit::<T>::zeroed();
+        let mut result: *mut T = std::ptr::null_mut();
+        let code = lookup(
+            entry.as_mut_ptr(),
+            buffer.as_mut_ptr(),
+            buffer.len(),
+            &mut result,
+        );
+
+        if code == libc::ERANGE {
+            buffer_len *= 2;
+            continue;
+        }
+        if code != 0 || result.is_null() {
+            return None;
+        }
+
+        let entry = unsafe { entry.assume_init() };
+        let name = name_of(&entry);
+        if name.is_null() {
+            return None;
+        }
+        return Some(
+            unsafe { std::ffi::CStr::from_ptr(name) }
+                .to_string_lossy()
+                .into_owned(),
+        );
+    }
+    None
+}
+
+#[cfg(unix)]
+fn resolve_user_name(uid: u32) -> Option<String> {
+    lookup_name::<libc::passwd>(
+        |entry, buffer, len, result| unsafe { libc::getpwuid_r(uid, entry, buffer, len, result) },
+        |entry| entry.pw_name,
+    )
+}
+
+#[cfg(unix)]
+fn resolve_group_name(gid: u32) -> Option<String> {
+    lookup_name::<libc::group>(
+        |entry, buffer, len, result| unsafe { libc::getgrgid_r(gid, entry, buffer, len, result) },
+        |entry| entry.gr_name,
+    )
+}
+
+#[cfg(not(unix))]
+fn resolve_user_name(_uid: u32) -> Option<String> {
+    None
+}
+
+#[cfg(not(unix))]
+fn resolve_group_name(_gid: u32) -> Option<String> {
+    None
+}
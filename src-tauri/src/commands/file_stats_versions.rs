@@ -0,0 +1,266 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::Metadata;
+use std::path::Path;
+use std::time::SystemTime;
+
+/// 报告中最多列出的版本簇数，按可节省空间排序
+const MAX_VERSION_CLUSTERS: usize = 200;
+
+/// 文件名末尾常见的版本、副本标记（小写比较）
+const VERSION_MARKERS: [&str; 24] = [
+    "final",
+    "real",
+    "latest",
+    "new",
+    "old",
+    "copy",
+    "backup",
+    "bak",
+    "draft",
+    "edited",
+    "updated",
+    "revised",
+    "fixed",
+    "orig",
+    "original",
+    "副本",
+    "终稿",
+    "最终版",
+    "最终",
+    "定稿",
+    "最新",
+    "修改",
+    "修改版",
+    "备份",
+];
+// 不含 `.`，避免把 v1.2 这类版本号拆开
+const NAME_SEPARATORS: [char; 3] = ['_', '-', ' '];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionFile {
+    pub path: String,
+    pub size: u64,
+    /// 修改时间（RFC 3339），无法获取时为空
+    pub modified: Option<String>,
+    /// 排序用的修改时间；本地时间字符串跨夏令时切换时顺序不对
+    #[serde(skip)]
+    modified_at: Option<SystemTime>,
+}
+
+/// 同一目录下归一化后同名的一组文件，按修改时间从新到旧排列
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionCluster {
+    pub directory: String,
+    /// 去掉版本标记后的名字，例如 `report.docx`
+    pub base_name: String,
+    pub files: Vec<VersionFile>,
+    pub total_size: u64,
+    /// 只保留最新一个版本时可以释放的空间
+    pub reclaimable_size: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct VersionReport {
+    /// 完整计数，`clusters` 只保留可节省空间最多的一部分
+    pub cluster_count: u64,
+    pub file_count: u64,
+    pub total_size: u64,
+    pub reclaimable_size: u64,
+    pub clusters: Vec<VersionCluster>,
+}
+
+impl VersionReport {
+    /// `candidates` 为同一目录下按 [`version_key`] 分组的文件
+    pub fn add_directory(&mut self, dir: &Path, candidates: HashMap<String, Vec<VersionFile>>) {
+        for (base_name, mut files) in candidates {
+            if files.len() < 2 {
+                continue;
+            }
+            files.sort_by(|a, b| {
+                b.modified_at
+                    .cmp(&a.modified_at)
+                    .then_with(|| a.path.cmp(&b.path))
+            });
+            let total_size: u64 = files.iter().map(|file| file.size).sum();
+            let reclaimable_size = total_size - files[0].size;
+
+            self.cluster_count += 1;
+            self.file_count += files.len() as u64;
+            self.total_size += total_size;
+            self.reclaimable_size += reclaimable_size;
+            self.clusters.push(VersionCluster {
+                directory: dir.display().to_string(),
+                base_name,
+                files,
+                total_size,
+                reclaimable_size,
+            });
+        }
+    }
+
+    pub fn merge(&mut self, other: VersionReport) {
+        self.cluster_count += other.cluster_count;
+        self.file_count += other.file_count;
+        self.total_size += other.total_size;
+        self.reclaimable_size += other.reclaimable_size;
+        self.clusters.extend(other.clusters);
+    }
+
+    /// 扫描结束后排序并截断列表
+    pub fn finish(mut self) -> VersionReport {
+        self.clusters.sort_by(|a, b| {
+            b.reclaimable_size
+                .cmp(&a.reclaimable_size)
+                .then_with(|| a.directory.cmp(&b.directory))
+                .then_with(|| a.base_name.cmp(&b.base_name))
+        });
+        self.clusters.truncate(MAX_VERSION_CLUSTERS);
+        self
+    }
+}
+
+pub fn version_file(path: &Path, metadata: &Metadata) -> VersionFile {
+    let modified_at = metadata.modified().ok();
+    VersionFile {
+        path: path.display().to_string(),
+        size: metadata.len(),
+        modified: modified_at
+            .map(|time| chrono::DateTime::<chrono::Local>::from(time).to_rfc3339()),
+        modified_at,
+    }
+}
+
+/// 去掉副本标记、版本号和编号后的归一化文件名（小写）
+///
+/// 只去掉末尾的标记，且至少保留第一段，例如 `report_final (2).docx`、
+/// `report_final_v3_REAL.docx` 都归一化为 `report.docx`；`photo_001.jpg`
+/// 这类普通编号不会被去掉。
+pub fn version_key(name: &str) -> String {
+    let lower = name.to_lowercase();
+    let (stem, extension) = match lower.rfind('.') {
+        Some(index) if index > 0 => (&lower[..index], &lower[index..]),
+        _ => (lower.as_str(), ""),
+    };
+    let mut stem = stem
+        .strip_prefix("copy of ")
+        .unwrap_or(stem)
+        .trim_end()
+        .to_string();
+
+    loop {
+        let trimmed = strip_version_suffix(&stem);
+        if trimmed.len() == stem.len() {
+            break;
+        }
+        stem = trimmed.to_string();
+    }
+    format!("{}{}", stem, extension)
+}
+
+/// 去掉末尾的一个标记：括号内容或以分隔符隔开的一段
+fn strip_version_suffix(stem: &str) -> &str {
+    for (open, close) in [('(', ')'), ('（', '）'), ('[', ']')] {
+        if let Some(inner_end) = stem.strip_suffix(close) {
+            if let Some(start) = inner_end.rfind(open) {
+                let inner = inner_end[start + open.len_utf8()..].trim();
+                let rest = stem[..start].trim_end_matches(NAME_SEPARATORS);
+                if !rest.is_empty() && (is_version_token(inner) || is_copy_number(inner)) {
+                    return rest;
+                }
+            }
+            return stem;
+        }
+    }
+
+    let Some(start) = stem.rfind(NAME_SEPARATORS) else {
+        return stem;
+    };
+    let rest = stem[..start].trim_end_matches(NAME_SEPARATORS);
+    if !rest.is_empty() && is_version_token(&stem[start + 1..]) {
+        rest
+    } else {
+        stem
+    }
+}
+
+fn is_version_token(token: &str) -> bool {
+    if token.is_empty() {
+        return false;
+    }
+    if VERSION_MARKERS.contains(&token) {
+        return true;
+    }
+    // v2、v1.3、rev4
+    let version = token
+        .strip_prefix("rev")
+        .or_else(|| token.strip_prefix('v'))
+        .unwrap_or("");
+    if !version.is_empty()
+        && version.starts_with(|ch: char| ch.is_ascii_digit())
+        && version.chars().all(|ch| ch.is_ascii_digit() || ch == '.')
+    {
+        return true;
+    }
+    // 20240501 这类日期
+    token.len() == 8 && token.chars().all(|ch| ch.is_ascii_digit())
+}
+
+/// 括号里的纯数字（`(2)`）只在括号形式下视为编号
+fn is_copy_number(token: &str) -> bool {
+    !token.is_empty() && token.len() <= 3 && token.chars().all(|ch| ch.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_key_strips_copy_markers_versions_and_numbering() {
+        assert_eq!(version_key("report_final.docx"), "report.docx");
+        assert_eq!(version_key("report_final (2).docx"), "report.docx");
+        assert_eq!(version_key("report_final_v3_REAL.docx"), "report.docx");
+        assert_eq!(version_key("Copy of report.docx"), "report.docx");
+        assert_eq!(version_key("report - 副本.docx"), "report.docx");
+        assert_eq!(version_key("方案（终稿）.pptx"), "方案.pptx");
+        assert_eq!(version_key("budget_v1.2_20240501.xlsx"), "budget.xlsx");
+    }
+
+    #[test]
+    fn version_key_keeps_ordinary_names() {
+        assert_eq!(version_key("photo_001.jpg"), "photo_001.jpg");
+        assert_eq!(version_key("final.txt"), "final.txt");
+        assert_eq!(version_key("new-york.md"), "new-york.md");
+        assert_eq!(
+            version_key("notes (draft ideas).md"),
+            "notes (draft ideas).md"
+        );
+        assert_ne!(version_key("report.docx"), version_key("report.pdf"));
+    }
+
+    #[test]
+    fn clusters_order_by_instant_across_offset_changes() {
+        // 夏令时结束前后：字符串较大的反而更早
+        let file = |path: &str, modified: &str| {
+            let time = chrono::DateTime::parse_from_rfc3339(modified).expect("valid time");
+            VersionFile {
+                path: path.into(),
+                size: 10,
+                modified: Some(modified.into()),
+                modified_at: Some(time.into()),
+            }
+        };
+        let mut report = VersionReport::default();
+        report.add_directory(
+            Path::new("docs"),
+            HashMap::from([(
+                "report.docx".to_string(),
+                vec![
+                    file("report_old.docx", "2024-10-27T02:30:00+02:00"),
+                    file("report_new.docx", "2024-10-27T02:10:00+01:00"),
+                ],
+            )]),
+        );
+        assert_eq!(report.clusters[0].files[0].path, "report_new.docx");
+    }
+}
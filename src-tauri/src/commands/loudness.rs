@@ -0,0 +1,313 @@
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter};
+
+use super::ffmpeg_utils::{get_ffmpeg_path, run_ffmpeg_analysis};
+use super::media_info::probe_media_info;
+use super::video::TimeRange;
+
+lazy_static::lazy_static! {
+    static ref LOUDNESS_CANCELLED: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
+    static ref LOUDNESS_PROCESS: Arc<Mutex<Option<u32>>> = Arc::new(Mutex::new(None));
+}
+
+// loudnorm 内部会升采样到 192kHz，输出前统一转回该采样率
+const NORMALIZED_SAMPLE_RATE: u32 = 48_000;
+/// 两遍标准化时第一遍测量占总进度的比例
+pub const MEASURE_PHASE_WEIGHT: f64 = 20.0;
+
+/// 响度标准化目标（EBU R128），所有字段均可省略
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct LoudnessTarget {
+    /// 目标综合响度（LUFS）
+    pub integrated_lufs: f64,
+    /// 真峰值上限（dBTP）
+    pub true_peak_db: f64,
+    /// 目标响度范围（LU）
+    pub loudness_range: f64,
+}
+
+impl Default for LoudnessTarget {
+    fn default() -> Self {
+        Self {
+            integrated_lufs: -16.0,
+            true_peak_db: -1.5,
+            loudness_range: 11.0,
+        }
+    }
+}
+
+impl LoudnessTarget {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(-70.0..=-5.0).contains(&self.integrated_lufs) {
+            return Err("目标响度应在 -70 到 -5 LUFS 之间".into());
+        }
+        if !(-9.0..=0.0).contains(&self.true_peak_db) {
+            return Err("真峰值上限应在 -9 到 0 dBTP 之间".into());
+        }
+        if !(1.0..=50.0).contains(&self.loudness_range) {
+            return Err("响度范围应在 1 到 50 LU 之间".into());
+        }
+        Ok(())
+    }
+}
+
+/// loudnorm 第一遍分析的测量结果
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+pub struct LoudnessMeasurement {
+    /// 综合响度（LUFS）
+    pub integrated_lufs: f64,
+    /// 真峰值（dBTP）
+    pub true_peak_db: f64,
+    /// 响度范围（LU）
+    pub loudness_range: f64,
+    pub threshold: f64,
+    /// 第二遍需要的增益修正
+    pub target_offset: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct LoudnormReport {
+    input_i: String,
+    input_tp: String,
+    input_lra: String,
+    input_thresh: String,
+    target_offset: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct LoudnessReportEvent<'a> {
+    input: &'a str,
+    measurement: &'a LoudnessMeasurement,
+}
+
+/// 用 loudnorm 第一遍分析第一条音轨在 `range` 内的响度
+pub fn measure_loudness<F>(
+    ffmpeg: &Path,
+    input: &str,
+    target: &LoudnessTarget,
+    range: TimeRange,
+    cancelled: &AtomicBool,
+    process: &Mutex<Option<u32>>,
+    on_progress: F,
+) -> Result<LoudnessMeasurement, String>
+where
+    F: FnMut(f64),
+{
+    target.validate()?;
+    let duration = range.end - range.start;
+    let args = vec![
+        "-hide_banner".to_string(),
+        "-nostats".to_string(),
+        "-ss".to_string(),
+        format!("{}", range.start),
+        "-t".to_string(),
+        format!("{}", duration),
+        "-i".to_string(),
+        input.to_string(),
+        "-map".to_string(),
+        "0:a:0".to_string(),
+        "-af".to_string(),
+        format!(
+            "loudnorm=I={}:TP={}:LRA={}:print_format=json",
+            target.integrated_lufs, target.true_peak_db, target.loudness_range
+        ),
+    ];
+
+    let log = run_ffmpeg_analysis(ffmpeg, &args, duration, cancelled, process, on_progress)
+        .map_err(|error| {
+            if error.contains("matches no streams") {
+                "没有音轨，无法分析响度".to_string()
+            } else {
+                error
+            }
+        })?;
+    parse_loudnorm_report(&log)
+}
+
+/// 取日志末尾 loudnorm 输出的 JSON 块
+fn parse_loudnorm_report(log: &str) -> Result<LoudnessMeasurement, String> {
+    let start = log.rfind('{').ok_or("未找到响度分析结果")?;
+    let end = log[start..].find('}').ok_or("未找到响度分析结果")? + start;
+    let report: LoudnormReport = serde_json::from_str(&log[start..=end])
+        .map_err(|e| format!("解析响度分析结果失败: {}", e))?;
+
+    let parse = |value: &str| value.trim().parse::<f64>().unwrap_or(f64::NEG_INFINITY);
+    let measurement = LoudnessMeasurement {
+        integrated_lufs: parse(&report.input_i),
+        true_peak_db: parse(&report.input_tp),
+        loudness_range: parse(&report.input_lra),
+        threshold: parse(&report.input_thresh),
+        target_offset: parse(&report.target_offset),
+    };
+    // 几乎无声的音频测出来是 -inf，第二遍无法使用
+    if !measurement.integrated_lufs.is_finite() || !measurement.threshold.is_finite() {
+        return Err("音频几乎无声，无法测量响度".into());
+    }
+    Ok(measurement)
+}
+
+/// 第二遍使用的滤镜：带入第一遍的测量值，优先线性增益
+pub fn loudnorm_filter(target: &LoudnessTarget, measured: &LoudnessMeasurement) -> String {
+    format!(
+        "loudnorm=I={}:TP={}:LRA={}:measured_I={:.2}:measured_TP={:.2}:measured_LRA={:.2}:measured_thresh={:.2}:offset={:.2}:linear=true,aresample={}",
+        target.integrated_lufs,
+        target.true_peak_db,
+        target.loudness_range,
+        measured.integrated_lufs,
+        measured.true_peak_db,
+        measured.loudness_range,
+        measured.threshold,
+        measured.target_offset,
+        NORMALIZED_SAMPLE_RATE
+    )
+}
+
+/// 记录测量值并通过 `loudness-measured` 事件上报，便于核对
+pub fn report_measurement(app: &AppHandle, input: &str, measurement: &LoudnessMeasurement) {
+    info!(
+        "[响度] {}: I={:.2} LUFS, TP={:.2} dBTP, LRA={:.2} LU, 阈值 {:.2}, 偏移 {:.2}",
+        input,
+        measurement.integrated_lufs,
+        measurement.true_peak_db,
+        measurement.loudness_range,
+        measurement.threshold,
+        measurement.target_offset
+    );
+    let _ = app.emit(
+        "loudness-measured",
+        LoudnessReportEvent { input, measurement },
+    );
+}
+
+/// 两遍标准化的第一遍：测量并上报，返回测量值和第二遍使用的 `-af` 滤镜
+pub fn prepare_normalization<F>(
+    app: &AppHandle,
+    input: &str,
+    target: &LoudnessTarget,
+    range: TimeRange,
+    cancelled: &AtomicBool,
+    process: &Mutex<Option<u32>>,
+    on_progress: F,
+) -> Result<(LoudnessMeasurement, String), String>
+where
+    F: FnMut(f64),
+{
+    let measurement = measure_loudness(
+        &get_ffmpeg_path(app),
+        input,
+        target,
+        range,
+        cancelled,
+        process,
+        on_progress,
+    )?;
+    report_measurement(app, input, &measurement);
+    Ok((measurement, loudnorm_filter(target, &measurement)))
+}
+
+/// 分析响度（EBU R128），返回综合响度、真峰值和响度范围
+///
+/// 可以只分析 `start_time` - `end_time` 之间的部分。进度通过 `loudness-progress` 事件上报，
+/// 使用 `cancel_loudness_analysis` 取消。
+#[tauri::command]
+pub async fn analyze_loudness(
+    app: AppHandle,
+    input: String,
+    target: Option<LoudnessTarget>,
+    start_time: Option<f64>,
+    end_time: Option<f64>,
+) -> Result<LoudnessMeasurement, String> {
+    let target = target.unwrap_or_default();
+    LOUDNESS_CANCELLED.store(false, Ordering::SeqCst);
+    let cancelled = LOUDNESS_CANCELLED.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let duration = probe_media_info(&app, &input)?
+            .container
+            .duration
+            .ok_or_else(|| "解析时长失败: 无法读取时长".to_string())?;
+        let range = TimeRange {
+            start: start_time.unwrap_or(0.0).max(0.0),
+            end: end_time.unwrap_or(duration).min(duration),
+        };
+        if range.end <= range.start {
+            return Err("结束时间必须大于开始时间".to_string());
+        }
+
+        let measurement = measure_loudness(
+            &get_ffmpeg_path(&app),
+            &input,
+            &target,
+            range,
+            &cancelled,
+            &LOUDNESS_PROCESS,
+            |progress| {
+                let _ = app.emit("loudness-progress", progress);
+            },
+        )?;
+        report_measurement(&app, &input, &measurement);
+        Ok(measurement)
+    })
+    .await
+    .map_err(|e| format!("任务执行失败: {}", e))?
+}
+
+/// 取消响度分析
+#[tauri::command]
+pub fn cancel_loudness_analysis() {
+    info!("[响度] 收到取消请求");
+    LOUDNESS_CANCELLED.store(true, Ordering::SeqCst);
+
+    if let Some(pid) = *LOUDNESS_PROCESS.lock().unwrap() {
+        #[cfg(unix)]
+        {
+            let _ = Command::new("kill").arg(pid.to_string()).status();
+        }
+        #[cfg(windows)]
+        {
+            let _ = Command::new("taskkill")
+                .args(["/PID", &pid.to_string(), "/F"])
+                .status();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_loudnorm_report_reads_trailing_json() {
+        let log = r#"[Parsed_loudnorm_0 @ 0x600]
+{
+	"input_i" : "-27.61",
+	"input_tp" : "-4.47",
+	"input_lra" : "18.06",
+	"input_thresh" : "-39.20",
+	"output_i" : "-16.58",
+	"output_tp" : "-1.50",
+	"output_lra" : "14.78",
+	"output_thresh" : "-27.71",
+	"normalization_type" : "dynamic",
+	"target_offset" : "0.58"
+}"#;
+        let measurement = parse_loudnorm_report(log).expect("report should parse");
+        assert_eq!(measurement.integrated_lufs, -27.61);
+        assert_eq!(measurement.true_peak_db, -4.47);
+        assert_eq!(measurement.loudness_range, 18.06);
+        assert_eq!(measurement.target_offset, 0.58);
+
+        let filter = loudnorm_filter(&LoudnessTarget::default(), &measurement);
+        assert!(filter.starts_with("loudnorm=I=-16:TP=-1.5:LRA=11:measured_I=-27.61:"));
+        assert!(filter.ends_with(":offset=0.58:linear=true,aresample=48000"));
+
+        let silent = log.replace("\"-27.61\"", "\"-inf\"");
+        assert!(parse_loudnorm_report(&silent).is_err());
+    }
+}
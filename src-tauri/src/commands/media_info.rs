@@ -0,0 +1,393 @@
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::process::Command;
+use tauri::AppHandle;
+
+use super::ffmpeg_utils::get_ffprobe_path;
+use super::video::parse_fps_value;
+
+#[derive(Debug, Deserialize)]
+struct ProbeOutput {
+    #[serde(default)]
+    streams: Vec<ProbeStream>,
+    format: Option<ProbeFormat>,
+    #[serde(default)]
+    chapters: Vec<ProbeChapter>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProbeStream {
+    index: u32,
+    codec_type: Option<String>,
+    codec_name: Option<String>,
+    codec_long_name: Option<String>,
+    profile: Option<String>,
+    bit_rate: Option<String>,
+    duration: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    pix_fmt: Option<String>,
+    color_space: Option<String>,
+    color_transfer: Option<String>,
+    color_primaries: Option<String>,
+    color_range: Option<String>,
+    avg_frame_rate: Option<String>,
+    r_frame_rate: Option<String>,
+    sample_rate: Option<String>,
+    channels: Option<u32>,
+    channel_layout: Option<String>,
+    #[serde(default)]
+    disposition: BTreeMap<String, i64>,
+    #[serde(default)]
+    tags: BTreeMap<String, String>,
+    #[serde(default)]
+    side_data_list: Vec<ProbeSideData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProbeSideData {
+    side_data_type: Option<String>,
+    rotation: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProbeFormat {
+    format_name: Option<String>,
+    format_long_name: Option<String>,
+    duration: Option<String>,
+    size: Option<String>,
+    bit_rate: Option<String>,
+    #[serde(default)]
+    tags: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProbeChapter {
+    start_time: Option<String>,
+    end_time: Option<String>,
+    #[serde(default)]
+    tags: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StreamKind {
+    Video,
+    Audio,
+    Subtitle,
+    Data,
+    Attachment,
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MediaInfo {
+    pub path: String,
+    pub container: ContainerInfo,
+    pub streams: Vec<StreamInfo>,
+    pub chapters: Vec<ChapterInfo>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ContainerInfo {
+    /// 例如 `mov,mp4,m4a,3gp,3g2,mj2`
+    pub format_name: Option<String>,
+    pub format_long_name: Option<String>,
+    pub duration: Option<f64>,
+    pub size: Option<u64>,
+    /// 整体码率（bit/s）
+    pub bit_rate: Option<u64>,
+    pub tags: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamInfo {
+    pub index: u32,
+    pub kind: StreamKind,
+    pub codec_name: Option<String>,
+    pub codec_long_name: Option<String>,
+    pub profile: Option<String>,
+    pub bit_rate: Option<u64>,
+    pub duration: Option<f64>,
+    pub language: Option<String>,
+    pub title: Option<String>,
+    pub is_default: bool,
+    pub tags: BTreeMap<String, String>,
+    /// 仅视频流
+    pub video: Option<VideoStreamInfo>,
+    /// 仅音频流
+    pub audio: Option<AudioStreamInfo>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VideoStreamInfo {
+    /// 编码尺寸，未按 `rotation` 旋转
+    pub width: u32,
+    pub height: u32,
+    pub fps: Option<f64>,
+    pub pix_fmt: Option<String>,
+    pub color_space: Option<String>,
+    pub color_transfer: Option<String>,
+    pub color_primaries: Option<String>,
+    pub color_range: Option<String>,
+    /// HDR 格式（`HDR10`、`HLG`、`Dolby Vision`），SDR 为空
+    pub hdr: Option<String>,
+    /// 播放时需要旋转的角度（顺时针，0/90/180/270）
+    pub rotation: i32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioStreamInfo {
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u32>,
+    pub channel_layout: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChapterInfo {
+    pub start: f64,
+    pub end: f64,
+    pub title: Option<String>,
+}
+
+/// 读取 ffprobe 的完整信息：容器、所有流和章节
+pub(crate) fn probe_media_info(app: &AppHandle, path: &str) -> Result<MediaInfo, String> {
+    let output = Command::new(get_ffprobe_path(app))
+        .args([
+            "-v",
+            "error",
+            "-print_format",
+            "json",
+            "-show_format",
+            "-show_streams",
+            "-show_chapters",
+            path,
+        ])
+        .output()
+        .map_err(|e| format!("执行 ffprobe 失败: {}", e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("读取媒体信息失败: {}", stderr.trim()));
+    }
+
+    let probe: ProbeOutput =
+        serde_json::from_slice(&output.stdout).map_err(|e| format!("解析媒体信息失败: {}", e))?;
+    Ok(build_media_info(path, probe))
+}
+
+fn build_media_info(path: &str, probe: ProbeOutput) -> MediaInfo {
+    let container = match probe.format {
+        Some(format) => ContainerInfo {
+            format_name: format.format_name,
+            format_long_name: format.format_long_name,
+            duration: parse_number(format.duration.as_deref()),
+            size: parse_number(format.size.as_deref()),
+            bit_rate: parse_number(format.bit_rate.as_deref()),
+            tags: format.tags,
+        },
+        None => ContainerInfo {
+            format_name: None,
+            format_long_name: None,
+            duration: None,
+            size: None,
+            bit_rate: None,
+            tags: BTreeMap::new(),
+        },
+    };
+
+    let chapters = probe
+        .chapters
+        .into_iter()
+        .filter_map(|chapter| {
+            Some(ChapterInfo {
+                start: parse_number(chapter.start_time.as_deref())?,
+                end: parse_number(chapter.end_time.as_deref())?,
+                title: chapter.tags.get("title").cloned(),
+            })
+        })
+        .collect();
+
+    MediaInfo {
+        path: path.to_string(),
+        container,
+        streams: probe.streams.into_iter().map(build_stream_info).collect(),
+        chapters,
+    }
+}
+
+fn build_stream_info(stream: ProbeStream) -> StreamInfo {
+    let kind = match stream.codec_type.as_deref() {
+        Some("video") => StreamKind::Video,
+        Some("audio") => StreamKind::Audio,
+        Some("subtitle") => StreamKind::Subtitle,
+        Some("data") => StreamKind::Data,
+        Some("attachment") => StreamKind::Attachment,
+        _ => StreamKind::Unknown,
+    };
+
+    let video = (kind == StreamKind::Video).then(|| VideoStreamInfo {
+        width: stream.width.unwrap_or(0),
+        height: stream.height.unwrap_or(0),
+        fps: stream
+            .avg_frame_rate
+            .as_deref()
+            .and_then(parse_fps_value)
+            .or_else(|| stream.r_frame_rate.as_deref().and_then(parse_fps_value)),
+        hdr: hdr_format(&stream),
+        rotation: stream_rotation(&stream),
+        pix_fmt: stream.pix_fmt.clone(),
+        color_space: stream.color_space.clone(),
+        color_transfer: stream.color_transfer.clone(),
+        color_primaries: stream.color_primaries.clone(),
+        color_range: stream.color_range.clone(),
+    });
+    let audio = (kind == StreamKind::Audio).then(|| AudioStreamInfo {
+        sample_rate: parse_number(stream.sample_rate.as_deref()),
+        channels: stream.channels,
+        channel_layout: stream.channel_layout.clone(),
+    });
+
+    StreamInfo {
+        index: stream.index,
+        kind,
+        codec_name: stream.codec_name,
+        codec_long_name: stream.codec_long_name,
+        profile: stream.profile,
+        bit_rate: parse_number(stream.bit_rate.as_deref()),
+        duration: parse_number(stream.duration.as_deref()),
+        language: stream
+            .tags
+            .get("language")
+            .filter(|language| language.as_str() != "und")
+            .cloned(),
+        title: stream.tags.get("title").cloned(),
+        is_default: stream
+            .disposition
+            .get("default")
+            .is_some_and(|value| *value != 0),
+        tags: stream.tags,
+        video,
+        audio,
+    }
+}
+
+/// 按传输特性和附加数据判断 HDR 格式
+fn hdr_format(stream: &ProbeStream) -> Option<String> {
+    let dolby_vision = stream.side_data_list.iter().any(|side_data| {
+        side_data
+            .side_data_type
+            .as_deref()
+            .is_some_and(|kind| kind.starts_with("DOVI"))
+    });
+    if dolby_vision {
+        return Some("Dolby Vision".into());
+    }
+    match stream.color_transfer.as_deref() {
+        Some("smpte2084") => Some("HDR10".into()),
+        Some("arib-std-b67") => Some("HLG".into()),
+        _ => None,
+    }
+}
+
+/// 新版 ffprobe 把旋转放在 Display Matrix 附加数据里（逆时针为正），旧版放在 `rotate` 标签里
+fn stream_rotation(stream: &ProbeStream) -> i32 {
+    let degrees = stream
+        .side_data_list
+        .iter()
+        .find_map(|side_data| side_data.rotation.map(|rotation| -rotation))
+        .or_else(|| parse_number::<f64>(stream.tags.get("rotate").map(String::as_str)))
+        .unwrap_or(0.0);
+    (degrees.round() as i32).rem_euclid(360)
+}
+
+/// ffprobe 的数值字段多为字符串，`N/A` 等无法解析的值返回 None
+fn parse_number<T: std::str::FromStr>(value: Option<&str>) -> Option<T> {
+    value?.trim().parse().ok()
+}
+
+/// 获取媒体文件的完整信息：容器格式、所有音视频/字幕流、章节和标签
+#[tauri::command]
+pub async fn inspect_media(app: AppHandle, path: String) -> Result<MediaInfo, String> {
+    let info = tokio::task::spawn_blocking(move || probe_media_info(&app, &path))
+        .await
+        .map_err(|e| format!("任务执行失败: {}", e))??;
+    debug!(
+        "[媒体信息] {}: {} 个流, {} 个章节",
+        info.path,
+        info.streams.len(),
+        info.chapters.len()
+    );
+    Ok(info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"{
+        "streams": [
+            {
+                "index": 0, "codec_type": "video", "codec_name": "hevc", "profile": "Main 10",
+                "width": 3840, "height": 2160, "pix_fmt": "yuv420p10le",
+                "color_space": "bt2020nc", "color_transfer": "smpte2084", "color_primaries": "bt2020",
+                "avg_frame_rate": "30000/1001", "r_frame_rate": "30000/1001", "bit_rate": "45000000",
+                "disposition": {"default": 1},
+                "tags": {"language": "und", "handler_name": "VideoHandler"},
+                "side_data_list": [{"side_data_type": "Display Matrix", "rotation": -90}]
+            },
+            {
+                "index": 1, "codec_type": "audio", "codec_name": "aac", "sample_rate": "48000",
+                "channels": 2, "channel_layout": "stereo", "bit_rate": "N/A",
+                "disposition": {"default": 0}, "tags": {"language": "eng", "title": "Commentary"}
+            },
+            {"index": 2, "codec_type": "subtitle", "codec_name": "mov_text", "tags": {"language": "chi"}}
+        ],
+        "format": {
+            "format_name": "mov,mp4,m4a,3gp,3g2,mj2", "duration": "120.120000",
+            "size": "675000000", "bit_rate": "44955044", "tags": {"title": "Trip"}
+        },
+        "chapters": [
+            {"id": 0, "start_time": "0.000000", "end_time": "60.000000", "tags": {"title": "Intro"}},
+            {"id": 1, "start_time": "60.000000", "end_time": "120.120000", "tags": {}}
+        ]
+    }"#;
+
+    #[test]
+    fn build_media_info_parses_streams_container_and_chapters() {
+        let probe: ProbeOutput = serde_json::from_str(SAMPLE).expect("sample should parse");
+        let info = build_media_info("/videos/trip.mp4", probe);
+
+        assert_eq!(info.container.duration, Some(120.12));
+        assert_eq!(info.container.bit_rate, Some(44_955_044));
+        assert_eq!(
+            info.container.tags.get("title").map(String::as_str),
+            Some("Trip")
+        );
+        assert_eq!(info.chapters.len(), 2);
+        assert_eq!(info.chapters[0].title.as_deref(), Some("Intro"));
+        assert_eq!(info.chapters[1].end, 120.12);
+
+        let video = &info.streams[0];
+        assert_eq!(video.kind, StreamKind::Video);
+        assert!(video.is_default);
+        assert_eq!(video.language, None);
+        let details = video.video.as_ref().expect("video details");
+        assert_eq!(details.hdr.as_deref(), Some("HDR10"));
+        assert_eq!(details.rotation, 90);
+        assert!((details.fps.unwrap_or_default() - 29.97).abs() < 0.01);
+
+        let audio = &info.streams[1];
+        assert_eq!(audio.bit_rate, None);
+        assert_eq!(audio.language.as_deref(), Some("eng"));
+        assert_eq!(audio.title.as_deref(), Some("Commentary"));
+        assert_eq!(
+            audio.audio.as_ref().and_then(|a| a.sample_rate),
+            Some(48_000)
+        );
+        assert!(audio.video.is_none());
+
+        assert_eq!(info.streams[2].kind, StreamKind::Subtitle);
+    }
+}
@@ -0,0 +1,528 @@
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter};
+
+use super::ffmpeg_utils::{
+    get_ffmpeg_path, get_ffprobe_path, run_ffmpeg_with_progress, write_concat_list,
+};
+use super::video::output_needs_faststart;
+
+lazy_static::lazy_static! {
+    static ref MERGE_CANCELLED: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
+    static ref MERGE_PROCESS: Arc<Mutex<Option<u32>>> = Arc::new(Mutex::new(None));
+}
+
+// 重新编码时统一的音频参数
+const MERGE_AUDIO_SAMPLE_RATE: u32 = 48_000;
+const MERGE_AUDIO_LAYOUT: &str = "stereo";
+
+#[derive(Debug, Deserialize)]
+struct ProbeOutput {
+    #[serde(default)]
+    streams: Vec<ProbeStream>,
+    format: Option<ProbeFormat>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProbeStream {
+    codec_type: Option<String>,
+    codec_name: Option<String>,
+    profile: Option<String>,
+    level: Option<i64>,
+    /// 需要 `-show_data_hash`，H.264/HEVC 的 SPS/PPS 等参数集都在这里
+    extradata_hash: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    pix_fmt: Option<String>,
+    time_base: Option<String>,
+    r_frame_rate: Option<String>,
+    sample_rate: Option<String>,
+    channels: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProbeFormat {
+    duration: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct VideoProfile {
+    codec: String,
+    profile: Option<String>,
+    level: Option<i64>,
+    extradata_hash: Option<String>,
+    width: u32,
+    height: u32,
+    pix_fmt: String,
+    time_base: String,
+    frame_rate: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct AudioProfile {
+    codec: String,
+    profile: Option<String>,
+    sample_rate: String,
+    channels: u32,
+}
+
+/// 判断能否无损拼接所需的流参数
+#[derive(Debug, Clone)]
+struct ClipProfile {
+    duration: f64,
+    /// 各流的类型，concat 分离器按流的序号对应拼接，顺序不同时不能直接复制
+    stream_layout: Vec<String>,
+    video: VideoProfile,
+    audio: Option<AudioProfile>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MergeResult {
+    pub output_path: String,
+    /// 是否直接复制流（未重新编码）
+    pub stream_copy: bool,
+    /// 需要重新编码时的原因
+    pub reencode_reason: Option<String>,
+}
+
+fn probe_clip(app: &AppHandle, path: &str) -> Result<ClipProfile, String> {
+    let output = Command::new(get_ffprobe_path(app))
+        .args([
+            "-v",
+            "error",
+            "-show_data_hash",
+            "CRC32",
+            "-show_entries",
+            "stream=codec_type,codec_name,profile,level,extradata_hash,width,height,pix_fmt,time_base,r_frame_rate,sample_rate,channels:format=duration",
+            "-of",
+            "json",
+            path,
+        ])
+        .output()
+        .map_err(|e| format!("执行 ffprobe 失败: {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("读取视频信息失败: {} ({})", path, stderr.trim()));
+    }
+
+    let probe: ProbeOutput = serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("解析视频信息失败: {} ({})", path, e))?;
+    parse_clip_profile(probe).map_err(|error| format!("{}: {}", error, path))
+}
+
+fn parse_clip_profile(probe: ProbeOutput) -> Result<ClipProfile, String> {
+    let duration = probe
+        .format
+        .and_then(|format| format.duration)
+        .and_then(|duration| duration.parse::<f64>().ok())
+        .filter(|duration| duration.is_finite() && *duration > 0.0)
+        .ok_or_else(|| "无法读取视频时长".to_string())?;
+
+    let video = probe
+        .streams
+        .iter()
+        .find(|stream| stream.codec_type.as_deref() == Some("video"))
+        .ok_or_else(|| "文件中没有视频流".to_string())?;
+    let audio = probe
+        .streams
+        .iter()
+        .find(|stream| stream.codec_type.as_deref() == Some("audio"));
+
+    Ok(ClipProfile {
+        duration,
+        stream_layout: probe
+            .streams
+            .iter()
+            .map(|stream| stream.codec_type.clone().unwrap_or_default())
+            .collect(),
+        video: VideoProfile {
+            codec: video.codec_name.clone().unwrap_or_default(),
+            profile: video.profile.clone(),
+            level: video.level,
+            extradata_hash: video.extradata_hash.clone(),
+            width: video.width.unwrap_or(0),
+            height: video.height.unwrap_or(0),
+            pix_fmt: video.pix_fmt.clone().unwrap_or_default(),
+            time_base: video.time_base.clone().unwrap_or_default(),
+            frame_rate: video.r_frame_rate.clone().unwrap_or_default(),
+        },
+        audio: audio.map(|audio| AudioProfile {
+            codec: audio.codec_name.clone().unwrap_or_default(),
+            profile: audio.profile.clone(),
+            sample_rate: audio.sample_rate.clone().unwrap_or_default(),
+            channels: audio.channels.unwrap_or(0),
+        }),
+    })
+}
+
+/// 所有片段的编码、参数集、分辨率、时间基等一致时才能直接复制流，否则返回第一个不一致的原因
+///
+/// concat 分离器只保留第一个文件的参数集（SPS/PPS），其余文件参数集不同时拼接后无法正确解码。
+fn stream_copy_mismatch(clips: &[ClipProfile]) -> Option<String> {
+    let first = clips.first()?;
+    for (index, clip) in clips.iter().enumerate().skip(1) {
+        let (a, b) = (&first.video, &clip.video);
+        let reason = if first.stream_layout != clip.stream_layout {
+            Some(format!(
+                "流的数量或顺序不同 ({} / {})",
+                first.stream_layout.join(","),
+                clip.stream_layout.join(",")
+            ))
+        } else if a.codec != b.codec {
+            Some(format!("视频编码不同 ({} / {})", a.codec, b.codec))
+        } else if (&a.profile, a.level) != (&b.profile, b.level) {
+            Some(format!(
+                "编码配置不同 ({} {} / {} {})",
+                a.profile.as_deref().unwrap_or("-"),
+                a.level.unwrap_or(0),
+                b.profile.as_deref().unwrap_or("-"),
+                b.level.unwrap_or(0)
+            ))
+        } else if a.extradata_hash != b.extradata_hash {
+            Some("视频编码参数集不同".to_string())
+        } else if (a.width, a.height) != (b.width, b.height) {
+            Some(format!(
+                "分辨率不同 ({}x{} / {}x{})",
+                a.width, a.height, b.width, b.height
+            ))
+        } else if a.pix_fmt != b.pix_fmt {
+            Some(format!("像素格式不同 ({} / {})", a.pix_fmt, b.pix_fmt))
+        } else if a.frame_rate != b.frame_rate {
+            Some(format!("帧率不同 ({} / {})", a.frame_rate, b.frame_rate))
+        } else if a.time_base != b.time_base {
+            Some(format!("时间基不同 ({} / {})", a.time_base, b.time_base))
+        } else if first.audio != clip.audio {
+            Some("音频参数不同".to_string())
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            return Some(format!("第 {} 个文件{}", index + 1, reason));
+        }
+    }
+    None
+}
+
+fn build_copy_args(list_path: &Path, output: &str) -> Vec<String> {
+    let mut args = vec![
+        "-y".to_string(),
+        "-f".to_string(),
+        "concat".to_string(),
+        "-safe".to_string(),
+        "0".to_string(),
+        "-i".to_string(),
+        list_path.to_string_lossy().to_string(),
+        "-map".to_string(),
+        "0:v:0".to_string(),
+        "-map".to_string(),
+        "0:a:0?".to_string(),
+        "-c".to_string(),
+        "copy".to_string(),
+    ];
+    if output_needs_faststart(output) {
+        args.extend(["-movflags".to_string(), "+faststart".to_string()]);
+    }
+    args
+}
+
+/// 重新编码：以第一个文件的分辨率和帧率为准，其余缩放并补黑边，音频统一为立体声，
+/// 没有音轨的片段补静音
+fn build_reencode_args(inputs: &[String], clips: &[ClipProfile], output: &str) -> Vec<String> {
+    let first = &clips[0].video;
+    // libx264 + yuv420p 要求宽高为偶数
+    let width = first.width.max(2) & !1;
+    let height = first.height.max(2) & !1;
+    let frame_rate = if first.frame_rate.is_empty() || first.frame_rate == "0/0" {
+        "30".to_string()
+    } else {
+        first.frame_rate.clone()
+    };
+    let with_audio = clips.iter().any(|clip| clip.audio.is_some());
+
+    let mut args = vec!["-y".to_string()];
+    for input in inputs {
+        args.extend(["-i".to_string(), input.clone()]);
+    }
+
+    let mut filters = Vec::new();
+    let mut concat_inputs = String::new();
+    for (index, clip) in clips.iter().enumerate() {
+        filters.push(format!(
+            "[{index}:v:0]scale={width}:{height}:force_original_aspect_ratio=decrease,\
+             pad={width}:{height}:(ow-iw)/2:(oh-ih)/2,setsar=1,fps={frame_rate},format=yuv420p[v{index}]"
+        ));
+        concat_inputs.push_str(&format!("[v{}]", index));
+        if with_audio {
+            if clip.audio.is_some() {
+                filters.push(format!(
+                    "[{index}:a:0]aresample={MERGE_AUDIO_SAMPLE_RATE},\
+                     aformat=sample_rates={MERGE_AUDIO_SAMPLE_RATE}:channel_layouts={MERGE_AUDIO_LAYOUT}[a{index}]"
+                ));
+            } else {
+                filters.push(format!(
+                    "anullsrc=r={MERGE_AUDIO_SAMPLE_RATE}:cl={MERGE_AUDIO_LAYOUT},atrim=duration={:.3}[a{index}]",
+                    clip.duration
+                ));
+            }
+            concat_inputs.push_str(&format!("[a{}]", index));
+        }
+    }
+    filters.push(format!(
+        "{}concat=n={}:v=1:a={}[v]{}",
+        concat_inputs,
+        clips.len(),
+        u8::from(with_audio),
+        if with_audio { "[a]" } else { "" }
+    ));
+
+    args.extend([
+        "-filter_complex".to_string(),
+        filters.join(";"),
+        "-map".to_string(),
+        "[v]".to_string(),
+    ]);
+    if with_audio {
+        args.extend([
+            "-map".to_string(),
+            "[a]".to_string(),
+            "-c:a".to_string(),
+            "aac".to_string(),
+            "-b:a".to_string(),
+            "192k".to_string(),
+        ]);
+    }
+    args.extend([
+        "-c:v".to_string(),
+        "libx264".to_string(),
+        "-crf".to_string(),
+        "20".to_string(),
+        "-preset".to_string(),
+        "fast".to_string(),
+    ]);
+    if output_needs_faststart(output) {
+        args.extend(["-movflags".to_string(), "+faststart".to_string()]);
+    }
+    args
+}
+
+fn build_temp_list_path() -> PathBuf {
+    let unique = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    std::env::temp_dir().join(format!("merge_{}_{}.txt", std::process::id(), unique))
+}
+
+/// 按顺序合并多个视频
+///
+/// 参数一致时直接复制流，否则（或 `force_reencode` 时、直接复制失败时）重新编码统一参数。
+/// 进度通过 `merge-progress` 事件上报，使用 `cancel_merge` 取消。
+#[tauri::command]
+pub async fn merge_videos(
+    app: AppHandle,
+    inputs: Vec<String>,
+    output: String,
+    force_reencode: Option<bool>,
+) -> Result<MergeResult, String> {
+    if inputs.len() < 2 {
+        return Err("请至少选择两个视频".into());
+    }
+    if inputs.iter().any(|input| input == &output) {
+        return Err("输出文件不能与输入文件相同".into());
+    }
+
+    MERGE_CANCELLED.store(false, Ordering::SeqCst);
+    let cancelled = MERGE_CANCELLED.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let clips = inputs
+            .iter()
+            .map(|input| probe_clip(&app, input))
+            .collect::<Result<Vec<_>, _>>()?;
+        let total_duration: f64 = clips.iter().map(|clip| clip.duration).sum();
+
+        let reencode_reason = if force_reencode.unwrap_or(false) {
+            Some("已选择重新编码".to_string())
+        } else {
+            stream_copy_mismatch(&clips)
+        };
+        info!(
+            "[合并] {} 个文件 -> {}, {}",
+            inputs.len(),
+            output,
+            reencode_reason.as_deref().unwrap_or("直接复制流")
+        );
+
+        let ffmpeg = get_ffmpeg_path(&app);
+        let run = |args: &[String]| {
+            run_ffmpeg_with_progress(
+                &ffmpeg,
+                args,
+                &output,
+                total_duration,
+                &cancelled,
+                &MERGE_PROCESS,
+                |progress| {
+                    let _ = app.emit("merge-progress", progress);
+                },
+            )
+        };
+
+        let mut reencode_reason = reencode_reason;
+        if reencode_reason.is_none() {
+            let list_path = build_temp_list_path();
+            let files: Vec<PathBuf> = inputs.iter().map(PathBuf::from).collect();
+            write_concat_list(&list_path, &files)?;
+            let result = run(&build_copy_args(&list_path, &output));
+            let _ = std::fs::remove_file(&list_path);
+            if let Err(error) = result {
+                if cancelled.load(Ordering::SeqCst) {
+                    return Err(error);
+                }
+                info!("[合并] 直接复制失败，改为重新编码: {}", error);
+                reencode_reason = Some("直接复制失败，已改为重新编码".to_string());
+            }
+        }
+        if reencode_reason.is_some() {
+            run(&build_reencode_args(&inputs, &clips, &output)).map_err(|error| {
+                if cancelled.load(Ordering::SeqCst) {
+                    error
+                } else {
+                    format!("视频合并失败: {}", error)
+                }
+            })?;
+        }
+
+        info!("[合并] 完成: {}", output);
+        Ok(MergeResult {
+            output_path: output,
+            stream_copy: reencode_reason.is_none(),
+            reencode_reason,
+        })
+    })
+    .await
+    .map_err(|e| format!("任务执行失败: {}", e))?
+}
+
+/// 取消合并
+#[tauri::command]
+pub fn cancel_merge() {
+    info!("[合并] 收到取消请求");
+    MERGE_CANCELLED.store(true, Ordering::SeqCst);
+
+    if let Some(pid) = *MERGE_PROCESS.lock().unwrap() {
+        #[cfg(unix)]
+        {
+            let _ = Command::new("kill").arg(pid.to_string()).status();
+        }
+        #[cfg(windows)]
+        {
+            let _ = Command::new("taskkill")
+                .args(["/PID", &pid.to_string(), "/F"])
+                .status();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clip(width: u32, height: u32, audio: bool) -> ClipProfile {
+        ClipProfile {
+            duration: 10.0,
+            stream_layout: if audio {
+                vec!["video".into(), "audio".into()]
+            } else {
+                vec!["video".into()]
+            },
+            video: VideoProfile {
+                codec: "h264".into(),
+                profile: Some("High".into()),
+                level: Some(40),
+                extradata_hash: Some("CRC32:1a2b3c4d".into()),
+                width,
+                height,
+                pix_fmt: "yuv420p".into(),
+                time_base: "1/15360".into(),
+                frame_rate: "30/1".into(),
+            },
+            audio: audio.then(|| AudioProfile {
+                codec: "aac".into(),
+                profile: Some("LC".into()),
+                sample_rate: "48000".into(),
+                channels: 2,
+            }),
+        }
+    }
+
+    #[test]
+    fn parse_clip_profile_reads_ffprobe_json() {
+        let probe: ProbeOutput = serde_json::from_str(
+            r#"{
+                "streams": [
+                    {"codec_type": "video", "codec_name": "h264", "width": 1920, "height": 1080,
+                     "pix_fmt": "yuv420p", "time_base": "1/15360", "r_frame_rate": "30/1"},
+                    {"codec_type": "audio", "codec_name": "aac", "sample_rate": "48000", "channels": 2}
+                ],
+                "format": {"duration": "12.500000"}
+            }"#,
+        )
+        .expect("probe json should parse");
+
+        let profile = parse_clip_profile(probe).expect("profile should parse");
+        assert_eq!(profile.duration, 12.5);
+        assert_eq!(profile.video.width, 1920);
+        assert_eq!(profile.audio.map(|audio| audio.channels), Some(2));
+    }
+
+    #[test]
+    fn stream_copy_requires_matching_profiles() {
+        assert_eq!(
+            stream_copy_mismatch(&[clip(1920, 1080, true), clip(1920, 1080, true)]),
+            None
+        );
+        assert_eq!(
+            stream_copy_mismatch(&[clip(1920, 1080, true), clip(1280, 720, true)]).as_deref(),
+            Some("第 2 个文件分辨率不同 (1920x1080 / 1280x720)")
+        );
+        assert!(stream_copy_mismatch(&[clip(1920, 1080, true), clip(1920, 1080, false)]).is_some());
+
+        let mut main_profile = clip(1920, 1080, true);
+        main_profile.video.profile = Some("Main".into());
+        assert_eq!(
+            stream_copy_mismatch(&[clip(1920, 1080, true), main_profile]).as_deref(),
+            Some("第 2 个文件编码配置不同 (High 40 / Main 40)")
+        );
+        let mut other_encoder = clip(1920, 1080, true);
+        other_encoder.video.extradata_hash = Some("CRC32:99887766".into());
+        assert!(stream_copy_mismatch(&[clip(1920, 1080, true), other_encoder]).is_some());
+        let mut swapped = clip(1920, 1080, true);
+        swapped.stream_layout.reverse();
+        assert!(stream_copy_mismatch(&[clip(1920, 1080, true), swapped]).is_some());
+    }
+
+    #[test]
+    fn reencode_args_pad_to_first_clip_and_fill_missing_audio() {
+        let inputs = vec!["a.mp4".to_string(), "b.mp4".to_string()];
+        let args = build_reencode_args(
+            &inputs,
+            &[clip(1920, 1080, true), clip(1281, 721, false)],
+            "out.mp4",
+        );
+        let filter = &args[args
+            .iter()
+            .position(|arg| arg == "-filter_complex")
+            .expect("filter_complex should be present")
+            + 1];
+
+        assert!(filter.contains("[1:v:0]scale=1920:1080:force_original_aspect_ratio=decrease"));
+        assert!(filter.contains("anullsrc=r=48000:cl=stereo,atrim=duration=10.000[a1]"));
+        assert!(filter.ends_with("[v0][a0][v1][a1]concat=n=2:v=1:a=1[v][a]"));
+        assert!(args.contains(&"+faststart".to_string()));
+    }
+}
@@ -2,7 +2,18 @@ pub mod convert;
 pub mod dedup;
 pub mod ffmpeg_utils;
 pub mod file_stats;
+pub mod file_stats_cleanup;
+pub mod file_stats_compression;
+pub mod file_stats_export;
+pub mod file_stats_health;
+pub mod file_stats_ownership;
+pub mod file_stats_versions;
 pub mod logger;
+pub mod loudness;
+pub mod media_info;
+pub mod merge;
+pub mod storage_growth;
 pub mod system;
 pub mod video;
+pub mod video_detect;
 pub mod watermark;
@@ -0,0 +1,379 @@
+use chrono::{DateTime, FixedOffset, Local};
+use log::{info, warn};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, Once};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use super::file_stats::{
+    has_foreground_scan, run_background_scan, BackgroundScanError, ScanOptions, ScanResult,
+};
+
+const TICK_INTERVAL: Duration = Duration::from_secs(60);
+const MIN_INTERVAL_MINUTES: u64 = 5;
+const MAX_SAMPLES_PER_FOLDER: usize = 2000;
+const TOP_EXTENSION_COUNT: usize = 5;
+
+lazy_static::lazy_static! {
+    // 命令和后台线程都会读写配置与历史文件，统一串行化
+    static ref STORAGE_GROWTH_LOCK: Mutex<()> = Mutex::new(());
+}
+
+static SCHEDULER_INIT: Once = Once::new();
+
+/// 需要定时统计的文件夹
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrowthFolder {
+    pub path: String,
+    pub interval_minutes: u64,
+    #[serde(default)]
+    pub options: ScanOptions,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtensionSize {
+    pub extension: String,
+    pub count: u64,
+    pub total_size: u64,
+}
+
+/// 时间序列中的一个采样点
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrowthSample {
+    pub scanned_at: String,
+    pub total_size: u64,
+    pub total_allocated_size: u64,
+    pub total_files: u64,
+    pub folder_count: u64,
+    pub top_extensions: Vec<ExtensionSize>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct FolderHistory {
+    path: String,
+    samples: Vec<GrowthSample>,
+}
+
+fn lock_storage() -> std::sync::MutexGuard<'static, ()> {
+    STORAGE_GROWTH_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn storage_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join("storage_growth"))
+        .map_err(|e| format!("获取应用数据目录失败: {}", e))
+}
+
+fn folders_file(dir: &Path) -> PathBuf {
+    dir.join("folders.json")
+}
+
+/// 每个文件夹单独一个历史文件，文件名取路径的哈希
+fn history_file(dir: &Path, folder_path: &str) -> PathBuf {
+    let hash = xxhash_rust::xxh3::xxh3_64(folder_path.as_bytes());
+    dir.join("history").join(format!("{:016x}.json", hash))
+}
+
+fn load_json<T: DeserializeOwned + Default>(path: &Path) -> Result<T, String> {
+    match fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content)
+            .map_err(|e| format!("解析 {} 失败: {}", path.display(), e)),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(T::default()),
+        Err(error) => Err(format!("读取 {} 失败: {}", path.display(), error)),
+    }
+}
+
+/// 先写临时文件再重命名，避免写到一半时崩溃导致历史丢失
+fn save_json<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建目录失败: {}", e))?;
+    }
+    let content = serde_json::to_string(value).map_err(|e| format!("序列化失败: {}", e))?;
+    let temp_path = path.with_extension("json.tmp");
+    fs::write(&temp_path, content).map_err(|e| format!("写入 {} 失败: {}", path.display(), e))?;
+    fs::rename(&temp_path, path).map_err(|e| format!("写入 {} 失败: {}", path.display(), e))
+}
+
+fn load_folders(dir: &Path) -> Result<Vec<GrowthFolder>, String> {
+    load_json(&folders_file(dir))
+}
+
+fn load_history(dir: &Path, folder_path: &str) -> Result<FolderHistory, String> {
+    let mut history: FolderHistory = load_json(&history_file(dir, folder_path))?;
+    history.path = folder_path.to_string();
+    Ok(history)
+}
+
+fn build_sample(result: &ScanResult) -> GrowthSample {
+    GrowthSample {
+        scanned_at: result.scanned_at.clone(),
+        total_size: result.total_size,
+        total_allocated_size: result.total_allocated_size,
+        total_files: result.total_files,
+        folder_count: result.folder_count,
+        top_extensions: result
+            .stats
+            .iter()
+            .take(TOP_EXTENSION_COUNT)
+            .map(|item| ExtensionSize {
+                extension: item.extension.clone(),
+                count: item.count,
+                total_size: item.total_size,
+            })
+            .collect(),
+    }
+}
+
+fn record_scan_result(dir: &Path, folder_path: &str, result: &ScanResult) -> Result<(), String> {
+    let _guard = lock_storage();
+    let mut history = load_history(dir, folder_path)?;
+    history.samples.push(build_sample(result));
+    let overflow = history.samples.len().saturating_sub(MAX_SAMPLES_PER_FOLDER);
+    history.samples.drain(..overflow);
+    save_json(&history_file(dir, folder_path), &history)
+}
+
+fn parse_timestamp(value: &str) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(value).ok()
+}
+
+fn is_due(
+    interval_minutes: u64,
+    last_run: Option<DateTime<FixedOffset>>,
+    now: DateTime<FixedOffset>,
+) -> bool {
+    let interval = chrono::Duration::minutes(interval_minutes.max(MIN_INTERVAL_MINUTES) as i64);
+    !matches!(last_run, Some(last_run) if now - last_run < interval)
+}
+
+/// 依次扫描到期的文件夹；有用户扫描进行时让路，被抢占的任务下一轮重试
+fn run_due_scans(dir: &Path, last_attempts: &mut HashMap<String, DateTime<FixedOffset>>) {
+    let folders = match load_folders(dir) {
+        Ok(folders) => folders,
+        Err(error) => {
+            warn!("[空间趋势] {}", error);
+            return;
+        }
+    };
+
+    for (index, folder) in folders.iter().enumerate() {
+        if has_foreground_scan() {
+            return;
+        }
+
+        let last_sample = {
+            let _guard = lock_storage();
+            load_history(dir, &folder.path)
+                .ok()
+                .and_then(|history| history.samples.last().cloned())
+                .and_then(|sample| parse_timestamp(&sample.scanned_at))
+        };
+        let last_run = last_sample.max(last_attempts.get(&folder.path).copied());
+        let now = Local::now().fixed_offset();
+        if !is_due(folder.interval_minutes, last_run, now) {
+            continue;
+        }
+
+        info!("[空间趋势] 开始后台扫描: {}", folder.path);
+        match run_background_scan(&folder.path, &format!("growth-{}", index), &folder.options) {
+            Ok(result) => {
+                last_attempts.insert(folder.path.clone(), now);
+                if let Err(error) = record_scan_result(dir, &folder.path, &result) {
+                    warn!("[空间趋势] 保存扫描结果失败: {}", error);
+                }
+            }
+            Err(BackgroundScanError::Cancelled) => {
+                info!("[空间趋势] 后台扫描被用户扫描抢占: {}", folder.path);
+                return;
+            }
+            Err(BackgroundScanError::Failed(error)) => {
+                // 失败后同样等待一个周期，避免每分钟重复扫描不可用的目录
+                last_attempts.insert(folder.path.clone(), now);
+                warn!("[空间趋势] 后台扫描失败: {} ({})", folder.path, error);
+            }
+        }
+    }
+}
+
+/// 启动后台定时扫描线程，随应用退出结束
+pub fn start_scheduler(app: AppHandle) {
+    SCHEDULER_INIT.call_once(|| {
+        std::thread::spawn(move || {
+            let mut last_attempts = HashMap::new();
+            loop {
+                std::thread::sleep(TICK_INTERVAL);
+                match storage_dir(&app) {
+                    Ok(dir) => run_due_scans(&dir, &mut last_attempts),
+                    Err(error) => warn!("[空间趋势] {}", error),
+                }
+            }
+        });
+    });
+}
+
+/// 获取定时统计的文件夹列表
+#[tauri::command]
+pub fn get_storage_growth_folders(app: AppHandle) -> Result<Vec<GrowthFolder>, String> {
+    let dir = storage_dir(&app)?;
+    let _guard = lock_storage();
+    load_folders(&dir)
+}
+
+/// 保存定时统计的文件夹列表（整体替换）
+#[tauri::command]
+pub fn set_storage_growth_folders(
+    app: AppHandle,
+    folders: Vec<GrowthFolder>,
+) -> Result<Vec<GrowthFolder>, String> {
+    let mut normalized: Vec<GrowthFolder> = Vec::with_capacity(folders.len());
+    for mut folder in folders {
+        folder.path = folder.path.trim().to_string();
+        if folder.path.is_empty() {
+            return Err("文件夹路径不能为空".into());
+        }
+        if folder.interval_minutes < MIN_INTERVAL_MINUTES {
+            return Err(format!("统计间隔不能小于 {} 分钟", MIN_INTERVAL_MINUTES));
+        }
+        if normalized.iter().any(|item| item.path == folder.path) {
+            continue;
+        }
+        normalized.push(folder);
+    }
+
+    let dir = storage_dir(&app)?;
+    let _guard = lock_storage();
+    save_json(&folders_file(&dir), &normalized)?;
+    info!("[空间趋势] 已更新定时统计文件夹: {} 个", normalized.len());
+    Ok(normalized)
+}
+
+/// 获取某个文件夹的历史采样，按时间先后排列
+#[tauri::command]
+pub fn get_storage_growth_series(
+    app: AppHandle,
+    path: String,
+) -> Result<Vec<GrowthSample>, String> {
+    let dir = storage_dir(&app)?;
+    let _guard = lock_storage();
+    Ok(load_history(&dir, &path)?.samples)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::file_stats::{FileStats, ScanExclusions};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    static NEXT_TEST_ID: AtomicUsize = AtomicUsize::new(0);
+
+    struct TestDir {
+        path: PathBuf,
+    }
+
+    impl TestDir {
+        fn new() -> Self {
+            let unique = format!(
+                "storage-growth-test-{}-{}-{}",
+                std::process::id(),
+                NEXT_TEST_ID.fetch_add(1, Ordering::Relaxed),
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_nanos()
+            );
+            let path = std::env::temp_dir().join(unique);
+            fs::create_dir_all(&path).expect("failed to create temp test directory");
+            Self { path }
+        }
+
+        fn path(&self) -> &Path {
+            &self.path
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+
+    fn scan_result(scanned_at: &str, total_size: u64) -> ScanResult {
+        ScanResult {
+            root_path: "/data".into(),
+            scanned_at: scanned_at.into(),
+            stats: (0..8)
+                .map(|index| FileStats {
+                    extension: format!(".ext{}", index),
+                    count: 1,
+                    total_size: total_size / 8,
+                    allocated_size: total_size / 8,
+                    sparse_count: 0,
+                    compression: None,
+                })
+                .collect(),
+            total_files: 8,
+            folder_count: 2,
+            total_size,
+            total_allocated_size: total_size,
+            type_count: 8,
+            sparse_files: 0,
+            sparse_samples: Vec::new(),
+            skipped_files: 0,
+            permission_denied_files: 0,
+            excluded: ScanExclusions::default(),
+            ownership: None,
+            health: None,
+            versions: None,
+            options: ScanOptions::default(),
+            sample_errors: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn is_due_respects_interval_and_minimum() {
+        let now = parse_timestamp("2024-05-01T12:00:00+08:00").expect("valid timestamp");
+        let recent = parse_timestamp("2024-05-01T11:58:00+08:00");
+        let old = parse_timestamp("2024-05-01T10:00:00+08:00");
+
+        assert!(is_due(60, None, now));
+        assert!(is_due(60, old, now));
+        assert!(!is_due(60, recent, now));
+        // 间隔低于下限时按下限计算
+        assert!(!is_due(1, recent, now));
+    }
+
+    #[test]
+    fn record_scan_result_appends_samples_with_top_extensions() {
+        let temp_dir = TestDir::new();
+
+        record_scan_result(
+            temp_dir.path(),
+            "/data",
+            &scan_result("2024-05-01T10:00:00+08:00", 800),
+        )
+        .expect("first sample should be recorded");
+        record_scan_result(
+            temp_dir.path(),
+            "/data",
+            &scan_result("2024-05-01T11:00:00+08:00", 1600),
+        )
+        .expect("second sample should be recorded");
+
+        let history = load_history(temp_dir.path(), "/data").expect("history should load");
+        assert_eq!(history.samples.len(), 2);
+        assert_eq!(history.samples[0].total_size, 800);
+        assert_eq!(history.samples[1].total_size, 1600);
+        assert_eq!(history.samples[1].top_extensions.len(), TOP_EXTENSION_COUNT);
+
+        let other = load_history(temp_dir.path(), "/other").expect("empty history should load");
+        assert!(other.samples.is_empty());
+    }
+}
@@ -7,20 +7,59 @@ use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime};
 use tauri::{AppHandle, Emitter};
 use walkdir::WalkDir;
 
-use super::ffmpeg_utils::{get_ffmpeg_path, get_ffprobe_path};
+use super::ffmpeg_utils::{
+    get_ffmpeg_path, get_ffprobe_path, parse_ffmpeg_time, run_ffmpeg_with_progress,
+    write_concat_list,
+};
 use super::logger::{log_error, log_info};
+use super::loudness::{
+    prepare_normalization, LoudnessMeasurement, LoudnessTarget, MEASURE_PHASE_WEIGHT,
+};
+use super::media_info::probe_media_info;
+use super::video_detect::{
+    detect_black_freeze, detect_scenes, detect_silence, ranges_without, BlackFreezeIntervals,
+    BlackFreezeOptions, SceneChange, SilenceOptions,
+};
 
 // 全局变量存储当前 FFmpeg 进程，用于取消
 lazy_static::lazy_static! {
     static ref VIDEO_CANCELLED: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
     static ref FFMPEG_PROCESS: Arc<Mutex<Option<u32>>> = Arc::new(Mutex::new(None));
     static ref BATCH_VIDEO_CANCELLED: Mutex<HashMap<String, Arc<AtomicBool>>> = Mutex::new(HashMap::new());
+    static ref KEYFRAME_INDEX_CACHE: Mutex<HashMap<String, CachedVideoPackets>> = Mutex::new(HashMap::new());
 }
 
 const SUPPORTED_VIDEO_EXTENSIONS: [&str; 7] = ["mp4", "mov", "avi", "mkv", "wmv", "flv", "webm"];
+// 短于该时长的片段直接丢弃，避免产生只有几帧的碎片
+const MIN_SEGMENT_DURATION: f64 = 0.05;
+// 多片段截取中切片阶段占总进度的比例，其余为拼接阶段
+const SEGMENT_PHASE_WEIGHT: f64 = 90.0;
+// 智能截取中切点与关键帧的容差（秒），小于该距离时不再单独重新编码
+const SMART_CUT_TOLERANCE: f64 = 0.01;
+// 直接复制相对重新编码的耗时比例，用于估算进度
+const SMART_CUT_COPY_WEIGHT: f64 = 0.05;
+// 每个检测阶段（静音、黑屏等）占单个文件进度的比例，其余为导出阶段
+const DETECT_PHASE_WEIGHT: f64 = 20.0;
+// 场景检测的默认灵敏度（scene 分数阈值）
+const DEFAULT_SCENE_THRESHOLD: f64 = 0.4;
+// 场景检测中分析阶段占总进度的比例，其余为生成缩略图
+const SCENE_DETECT_WEIGHT: f64 = 80.0;
+// 最多为多少个场景切换生成缩略图
+const MAX_SCENE_THUMBNAILS: usize = 200;
+// 拆分的最大段数，与 `_part001` 的三位编号对应
+const MAX_SPLIT_PARTS: usize = 999;
+// 按大小拆分时预留的余量，码率不均匀时各段也尽量不超过目标大小
+const SPLIT_SIZE_MARGIN: f64 = 0.95;
+// 最多缓存的关键帧索引数
+const MAX_KEYFRAME_INDEX_CACHE: usize = 16;
+// 计算快速截取对齐点时，先向前读取的时长（秒）
+const SNAP_PROBE_WINDOW: f64 = 30.0;
+// 精确预览时先定位到目标时间之前多少秒，再逐帧解码到目标时间
+const ACCURATE_SEEK_PREROLL: f64 = 3.0;
 
 fn lock_batch_cancelled_tasks() -> std::sync::MutexGuard<'static, HashMap<String, Arc<AtomicBool>>>
 {
@@ -84,7 +123,7 @@ fn build_temp_preview_path() -> std::path::PathBuf {
     std::env::temp_dir().join(format!("preview_{}_{}.jpg", std::process::id(), unique))
 }
 
-fn parse_fps_value(value: &str) -> Option<f64> {
+pub(crate) fn parse_fps_value(value: &str) -> Option<f64> {
     let trimmed = value.trim();
     if trimmed.is_empty() {
         return None;
@@ -113,7 +152,7 @@ fn parse_fps_value(value: &str) -> Option<f64> {
     }
 }
 
-fn output_needs_faststart(path: &str) -> bool {
+pub(crate) fn output_needs_faststart(path: &str) -> bool {
     matches!(
         path.rsplit('.').next().map(|ext| ext.to_ascii_lowercase()),
         Some(ext) if ext == "mp4" || ext == "mov" || ext == "m4v"
@@ -126,21 +165,29 @@ fn generate_preview_frame_with_options(
     time: f64,
     max_width: Option<u32>,
     quality: u8,
+    accurate: bool,
 ) -> Result<String, String> {
     let ffmpeg = get_ffmpeg_path(app);
     let temp_file = build_temp_preview_path();
     let temp_path = temp_file.to_string_lossy().to_string();
 
+    // 部分容器的输入端定位不准，精确模式先定位到目标之前，再解码到目标帧
+    let input_seek = if accurate {
+        (time - ACCURATE_SEEK_PREROLL).max(0.0)
+    } else {
+        time
+    };
     let mut args = vec![
         "-y".to_string(),
         "-ss".to_string(),
-        format!("{}", time),
+        format!("{}", input_seek),
         "-i".to_string(),
         path.to_string(),
-        "-an".to_string(),
-        "-sn".to_string(),
-        "-dn".to_string(),
     ];
+    if accurate {
+        args.extend(["-ss".to_string(), format!("{}", time - input_seek)]);
+    }
+    args.extend(["-an".to_string(), "-sn".to_string(), "-dn".to_string()]);
 
     if let Some(width) = max_width {
         args.push("-vf".to_string());
@@ -176,10 +223,21 @@ fn generate_preview_frame_with_options(
 }
 
 /// 生成视频预览帧（返回 base64 编码的图片）
+///
+/// `accurate` 为 true 时解码到目标时间的那一帧，否则可能显示附近关键帧的画面。
 #[tauri::command]
-pub fn generate_preview_frame(app: AppHandle, path: String, time: f64) -> Result<String, String> {
-    debug!("[预览] 生成预览帧: {} @ {:.2}s", path, time);
-    generate_preview_frame_with_options(&app, &path, time, None, 2)
+pub fn generate_preview_frame(
+    app: AppHandle,
+    path: String,
+    time: f64,
+    accurate: Option<bool>,
+) -> Result<String, String> {
+    let accurate = accurate.unwrap_or(false);
+    debug!(
+        "[预览] 生成预览帧: {} @ {:.2}s (精确: {})",
+        path, time, accurate
+    );
+    generate_preview_frame_with_options(&app, &path, time, None, 2, accurate)
 }
 
 /// 生成多个预览帧（用于时间轴）
@@ -204,7 +262,7 @@ pub fn generate_timeline_frames(
 
     for i in 1..=count {
         let time = interval * (i as f64);
-        match generate_preview_frame_with_options(&app, &path, time, Some(360), 6) {
+        match generate_preview_frame_with_options(&app, &path, time, Some(360), 6, false) {
             Ok(frame) => frames.push(frame),
             Err(_) => continue,
         }
@@ -258,64 +316,22 @@ pub fn get_video_duration(app: AppHandle, path: String) -> Result<f64, String> {
     })
 }
 
-/// 获取视频信息
+/// 获取视频信息（`inspect_media` 的精简版本）
 #[tauri::command]
 pub fn get_video_info(app: AppHandle, path: String) -> Result<VideoInfo, String> {
-    let ffprobe = get_ffprobe_path(&app);
-    let duration = get_video_duration(app, path.clone())?;
-
-    let output = Command::new(&ffprobe)
-        .args([
-            "-v",
-            "error",
-            "-select_streams",
-            "v:0",
-            "-show_entries",
-            "stream=width,height,avg_frame_rate,r_frame_rate",
-            "-of",
-            "csv=p=0",
-            &path,
-        ])
-        .output()
-        .map_err(|e| format!("执行 ffprobe 失败: {}", e))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("读取视频流信息失败: {}", stderr.trim()));
-    }
-
-    let info_str = String::from_utf8_lossy(&output.stdout);
-    let parts: Vec<&str> = info_str.trim().split(',').collect();
-
-    let (width, height, fps) = if parts.len() >= 4 {
-        let w = parts[0]
-            .parse()
-            .map_err(|e| format!("解析视频宽度失败: {}", e))?;
-        let h = parts[1]
-            .parse()
-            .map_err(|e| format!("解析视频高度失败: {}", e))?;
-        let fps = parse_fps_value(parts[2])
-            .or_else(|| parse_fps_value(parts[3]))
-            .unwrap_or(30.0);
-        (w, h, fps)
-    } else if parts.len() >= 3 {
-        let w = parts[0]
-            .parse()
-            .map_err(|e| format!("解析视频宽度失败: {}", e))?;
-        let h = parts[1]
-            .parse()
-            .map_err(|e| format!("解析视频高度失败: {}", e))?;
-        let fps = parse_fps_value(parts[2]).unwrap_or(30.0);
-        (w, h, fps)
-    } else {
-        (0, 0, 30.0)
-    };
+    let info = probe_media_info(&app, &path)?;
+    let duration = info
+        .container
+        .duration
+        .ok_or_else(|| "解析时长失败: 无法读取时长".to_string())?;
+    let video = info.streams.iter().find_map(|stream| stream.video.as_ref());
 
     Ok(VideoInfo {
         duration,
-        width,
-        height,
-        fps,
+        width: video.map_or(0, |video| video.width),
+        height: video.map_or(0, |video| video.height),
+        fps: video.and_then(|video| video.fps).unwrap_or(30.0),
+        rotation: video.map_or(0, |video| video.rotation),
     })
 }
 
@@ -325,6 +341,8 @@ pub struct VideoInfo {
     pub width: u32,
     pub height: u32,
     pub fps: f64,
+    /// 顺时针旋转角度，`width`、`height` 为旋转前的编码尺寸
+    pub rotation: i32,
 }
 
 #[derive(Debug, Serialize)]
@@ -347,6 +365,18 @@ pub struct BatchTrimItemResult {
     pub output_path: Option<String>,
     pub status: String,
     pub message: String,
+    /// 开启自动去除黑屏/静止画面时的检测结果
+    pub auto_trim: Option<AutoTrimReport>,
+    /// 开启响度标准化时第一遍的测量值
+    pub loudness: Option<LoudnessMeasurement>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AutoTrimReport {
+    pub intervals: BlackFreezeIntervals,
+    /// 去掉开头结尾的黑屏/静止画面后实际保留的范围
+    pub start: f64,
+    pub end: f64,
 }
 
 #[derive(Debug, Serialize)]
@@ -372,6 +402,136 @@ pub struct BatchTrimProgress {
     pub failed: usize,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct TimeRange {
+    pub start: f64,
+    pub end: f64,
+}
+
+/// 批量截取规则：删除片头、片尾，或只保留固定时间段（优先于片头片尾）
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct BatchTrimSpec {
+    trim_start: f64,
+    trim_end: f64,
+    keep_range: Option<TimeRange>,
+    /// 在保留的范围内再去掉静音
+    remove_silence: Option<SilenceOptions>,
+    /// 按检测结果去掉保留范围开头结尾的黑屏/静止画面
+    auto_trim: Option<BlackFreezeOptions>,
+    /// 按保留范围的响度两遍标准化音频
+    normalize_loudness: Option<LoudnessTarget>,
+}
+
+/// 单个文件的覆盖设置，未填写的项沿用全局设置
+#[derive(Debug, Deserialize, Clone)]
+pub struct BatchTrimOverride {
+    pub path: String,
+    pub trim_start: Option<f64>,
+    pub trim_end: Option<f64>,
+    pub keep_range: Option<TimeRange>,
+}
+
+impl BatchTrimSpec {
+    fn with_override(mut self, item: &BatchTrimOverride) -> Self {
+        if let Some(trim_start) = item.trim_start {
+            self.trim_start = trim_start;
+        }
+        if let Some(trim_end) = item.trim_end {
+            self.trim_end = trim_end;
+        }
+        if item.keep_range.is_some() {
+            self.keep_range = item.keep_range;
+        }
+        self
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.trim_start < 0.0 || self.trim_end < 0.0 {
+            return Err("删除的时长不能为负数".into());
+        }
+        if let Some(silence) = &self.remove_silence {
+            silence.validate()?;
+        }
+        if let Some(auto_trim) = &self.auto_trim {
+            auto_trim.validate()?;
+        }
+        if let Some(target) = &self.normalize_loudness {
+            target.validate()?;
+        }
+        match self.keep_range {
+            Some(range) if range.start < 0.0 || range.end <= range.start => {
+                Err("保留范围的结束时间必须大于开始时间".into())
+            }
+            None if self.trim_start <= 0.0
+                && self.trim_end <= 0.0
+                && self.remove_silence.is_none()
+                && self.auto_trim.is_none()
+                && self.normalize_loudness.is_none() =>
+            {
+                Err("请先设定要删除的片头、片尾时长、保留范围，或开启自动去除".into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// 按文件时长计算要保留的区间，时长不够时返回跳过原因
+    fn resolve(&self, duration: f64) -> Result<(f64, f64), String> {
+        if let Some(range) = self.keep_range {
+            let end = range.end.min(duration);
+            if end - range.start < MIN_SEGMENT_DURATION {
+                return Err(format!(
+                    "视频时长 {:.1}s 不包含保留范围 {:.1}s - {:.1}s",
+                    duration, range.start, range.end
+                ));
+            }
+            return Ok((range.start, end));
+        }
+
+        let end = duration - self.trim_end;
+        if end <= self.trim_start + 0.001 {
+            return Err(if self.trim_end > 0.0 {
+                format!(
+                    "视频时长 {:.1}s 不足以删除前 {:.1}s 和后 {:.1}s",
+                    duration, self.trim_start, self.trim_end
+                )
+            } else {
+                format!(
+                    "视频时长 {:.1}s 不足以删除前 {:.1}s",
+                    duration, self.trim_start
+                )
+            });
+        }
+        Ok((self.trim_start, end))
+    }
+}
+
+/// 拆分方式
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SplitMode {
+    Duration,
+    Size,
+    Chapters,
+}
+
+/// 拆分出的一段，时长和大小均为导出后的实际值
+#[derive(Debug, Serialize)]
+pub struct SplitPart {
+    pub path: String,
+    /// 在原视频中的起始时间（秒）
+    pub start: f64,
+    pub duration: f64,
+    pub size: u64,
+}
+
+/// `ranges` 表示要保留的片段，还是要删除的片段
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SegmentMode {
+    Keep,
+    Remove,
+}
+
 fn create_unique_output_path(
     input: &Path,
     output_dir: &Path,
@@ -458,13 +618,38 @@ fn emit_batch_progress(
     );
 }
 
-fn run_fast_cut(
-    app: &AppHandle,
-    input: &str,
-    output: &str,
-    start_time: f64,
-    end_time: f64,
-) -> Result<(), String> {
+/// 要导出的音轨：响度只测量第一条音轨，标准化时只保留这一条，避免把同一增益套到其他音轨上
+fn audio_stream_map(audio_filter: Option<&str>) -> &'static str {
+    if audio_filter.is_some() {
+        "0:a:0?"
+    } else {
+        "0:a?"
+    }
+}
+
+/// 一次截取：把 `input` 的 `range` 导出到 `output`，`audio_filter` 为音频滤镜（如响度标准化）
+#[derive(Clone, Copy)]
+struct CutJob<'a> {
+    input: &'a str,
+    output: &'a str,
+    range: TimeRange,
+    audio_filter: Option<&'a str>,
+    cancelled: &'a AtomicBool,
+}
+
+/// 直接复制截取；设置 `audio_filter` 时视频仍直接复制，音频按滤镜重新编码
+fn run_fast_cut<F>(app: &AppHandle, job: CutJob, on_progress: F) -> Result<(), String>
+where
+    F: FnMut(f64),
+{
+    let CutJob {
+        input,
+        output,
+        range,
+        audio_filter,
+        cancelled,
+    } = job;
+    let (start_time, end_time) = (range.start, range.end);
     if end_time <= start_time {
         return Err("结束时间必须大于开始时间".into());
     }
@@ -484,53 +669,61 @@ fn run_fast_cut(
         "-map".to_string(),
         "0:v:0".to_string(),
         "-map".to_string(),
-        "0:a?".to_string(),
+        audio_stream_map(audio_filter).to_string(),
         "-map_metadata".to_string(),
         "0".to_string(),
-        "-c".to_string(),
-        "copy".to_string(),
-        "-avoid_negative_ts".to_string(),
-        "make_zero".to_string(),
     ];
 
+    match audio_filter {
+        Some(filter) => args.extend([
+            "-c:v".to_string(),
+            "copy".to_string(),
+            "-c:a".to_string(),
+            "aac".to_string(),
+            "-b:a".to_string(),
+            "192k".to_string(),
+            "-af".to_string(),
+            filter.to_string(),
+        ]),
+        None => args.extend(["-c".to_string(), "copy".to_string()]),
+    }
+    args.extend(["-avoid_negative_ts".to_string(), "make_zero".to_string()]);
+
     if use_faststart {
         args.push("-movflags".to_string());
         args.push("+faststart".to_string());
     }
 
-    args.push(output.to_string());
-
-    let result = Command::new(&ffmpeg)
-        .args(&args)
-        .stdout(Stdio::null())
-        .stderr(Stdio::piped())
-        .output()
-        .map_err(|error| format!("执行 ffmpeg 失败: {}", error))?;
-
-    if result.status.success() {
-        Ok(())
-    } else {
-        let stderr = String::from_utf8_lossy(&result.stderr).trim().to_string();
-        if stderr.is_empty() {
-            Err("视频截取失败".into())
+    run_ffmpeg_with_progress(
+        &ffmpeg,
+        &args,
+        output,
+        duration,
+        cancelled,
+        &FFMPEG_PROCESS,
+        on_progress,
+    )
+    .map_err(|error| {
+        if cancelled.load(Ordering::SeqCst) {
+            error
         } else {
-            Err(format!("视频截取失败: {}", stderr))
+            format!("视频截取失败: {}", error)
         }
-    }
+    })
 }
 
-fn run_precise_cut<F>(
-    app: &AppHandle,
-    input: &str,
-    output: &str,
-    start_time: f64,
-    end_time: f64,
-    cancelled: &AtomicBool,
-    mut on_progress: F,
-) -> Result<(), String>
+fn run_precise_cut<F>(app: &AppHandle, job: CutJob, mut on_progress: F) -> Result<(), String>
 where
     F: FnMut(f64),
 {
+    let CutJob {
+        input,
+        output,
+        range,
+        audio_filter,
+        cancelled,
+    } = job;
+    let (start_time, end_time) = (range.start, range.end);
     if end_time <= start_time {
         return Err("结束时间必须大于开始时间".into());
     }
@@ -550,7 +743,7 @@ where
         "-map".to_string(),
         "0:v:0".to_string(),
         "-map".to_string(),
-        "0:a?".to_string(),
+        audio_stream_map(audio_filter).to_string(),
         "-map_metadata".to_string(),
         "0".to_string(),
         "-c:v".to_string(),
@@ -569,6 +762,11 @@ where
         "make_zero".to_string(),
     ];
 
+    if let Some(filter) = audio_filter {
+        args.push("-af".to_string());
+        args.push(filter.to_string());
+    }
+
     if use_faststart {
         args.push("-movflags".to_string());
         args.push("+faststart".to_string());
@@ -642,180 +840,1293 @@ where
     cut_result
 }
 
-#[tauri::command]
-pub fn collect_batch_video_files(inputs: Vec<String>) -> Result<Vec<BatchVideoFile>, String> {
-    if inputs.is_empty() {
-        return Ok(Vec::new());
-    }
+/// 开启响度标准化时先测量 `range` 内的响度，返回第二遍使用的音频滤镜
+fn prepare_cut_normalization<F>(
+    app: &AppHandle,
+    input: &str,
+    target: Option<&LoudnessTarget>,
+    range: TimeRange,
+    cancelled: &AtomicBool,
+    on_progress: F,
+) -> Result<Option<(LoudnessMeasurement, String)>, String>
+where
+    F: FnMut(f64),
+{
+    target
+        .map(|target| {
+            prepare_normalization(
+                app,
+                input,
+                target,
+                range,
+                cancelled,
+                &FFMPEG_PROCESS,
+                on_progress,
+            )
+        })
+        .transpose()
+}
 
-    let mut seen = HashSet::new();
-    let mut items = Vec::new();
+/// 把用户给出的时间段整理为按时间排序、互不重叠的保留片段
+fn resolve_keep_ranges(
+    ranges: &[TimeRange],
+    mode: SegmentMode,
+    duration: f64,
+) -> Result<Vec<TimeRange>, String> {
+    let mut normalized: Vec<TimeRange> = ranges
+        .iter()
+        .filter(|range| range.start.is_finite() && range.end.is_finite())
+        .map(|range| TimeRange {
+            start: range.start.clamp(0.0, duration),
+            end: range.end.clamp(0.0, duration),
+        })
+        .filter(|range| range.end > range.start)
+        .collect();
+    if normalized.is_empty() {
+        return Err("请至少设定一个有效的时间段".into());
+    }
 
-    for input in inputs {
-        let path = PathBuf::from(&input);
-        let metadata = match std::fs::metadata(&path) {
-            Ok(metadata) => metadata,
-            Err(_) => continue,
-        };
+    normalized.sort_by(|a, b| a.start.total_cmp(&b.start));
+    let mut merged: Vec<TimeRange> = Vec::with_capacity(normalized.len());
+    for range in normalized {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
 
-        if metadata.is_dir() {
-            for entry in WalkDir::new(&path).into_iter().filter_map(Result::ok) {
-                if !entry.file_type().is_file() || !is_supported_video_path(entry.path()) {
-                    continue;
-                }
-                let entry_path = entry.path().to_string_lossy().to_string();
-                if !seen.insert(entry_path.clone()) {
-                    continue;
-                }
-                let entry_meta = match entry.metadata() {
-                    Ok(meta) => meta,
-                    Err(_) => continue,
-                };
-                items.push(BatchVideoFile {
-                    name: entry
-                        .path()
-                        .file_name()
-                        .map(|value| value.to_string_lossy().to_string())
-                        .unwrap_or_default(),
-                    path: entry_path,
-                    size: entry_meta.len(),
-                });
-            }
-        } else if metadata.is_file() && is_supported_video_path(&path) {
-            let normalized = path.to_string_lossy().to_string();
-            if seen.insert(normalized.clone()) {
-                items.push(BatchVideoFile {
-                    name: path
-                        .file_name()
-                        .map(|value| value.to_string_lossy().to_string())
-                        .unwrap_or_default(),
-                    path: normalized,
-                    size: metadata.len(),
+    let mut keep = match mode {
+        SegmentMode::Keep => merged,
+        SegmentMode::Remove => {
+            let mut keep = Vec::with_capacity(merged.len() + 1);
+            let mut cursor = 0.0;
+            for range in merged {
+                keep.push(TimeRange {
+                    start: cursor,
+                    end: range.start,
                 });
+                cursor = range.end;
             }
+            keep.push(TimeRange {
+                start: cursor,
+                end: duration,
+            });
+            keep
         }
+    };
+    keep.retain(|range| range.end - range.start >= MIN_SEGMENT_DURATION);
+
+    if keep.is_empty() {
+        return Err(match mode {
+            SegmentMode::Keep => "所选时间段过短".into(),
+            SegmentMode::Remove => "删除这些片段后没有剩余内容".into(),
+        });
     }
+    Ok(keep)
+}
 
-    items.sort_by(|a, b| a.path.cmp(&b.path));
-    Ok(items)
+/// 中间片段所在的临时目录，放在输出目录旁边，结束时（包括出错和取消）自动删除
+struct SegmentWorkDir {
+    path: PathBuf,
 }
 
-#[tauri::command]
-pub async fn batch_trim_videos(
-    app: AppHandle,
-    task_id: String,
-    paths: Vec<String>,
-    trim_start: f64,
-    precise_mode: bool,
-    output_mode: BatchVideoOutputMode,
-    output_dir: Option<String>,
-    suffix: Option<String>,
-) -> Result<BatchTrimResult, String> {
-    if paths.is_empty() {
-        return Err("请先选择要处理的视频".into());
+impl SegmentWorkDir {
+    fn create(output: &Path) -> Result<Self, String> {
+        let parent = output
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .unwrap_or_else(|| Path::new("."));
+        let unique = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        let path = parent.join(format!(".segments_{}_{}", std::process::id(), unique));
+        std::fs::create_dir_all(&path).map_err(|error| format!("创建临时目录失败: {}", error))?;
+        Ok(Self { path })
     }
-    if trim_start <= 0.0 {
-        return Err("请先设定要删除的片头时长".into());
+
+    fn path(&self) -> &Path {
+        &self.path
     }
+}
 
-    let cancelled = register_batch_task(&task_id);
-    let task_id_for_cleanup = task_id.clone();
-    let suffix = suffix.unwrap_or_else(|| "_trim".into());
+impl Drop for SegmentWorkDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
 
-    let task_result = tokio::task::spawn_blocking(move || {
-        let total = paths.len();
-        let target_directory = output_dir.map(PathBuf::from);
-        let mut succeeded = 0usize;
-        let mut skipped = 0usize;
-        let mut failed = 0usize;
-        let mut items = Vec::with_capacity(total);
+/// 按 concat 列表无损拼接，各片段时间戳依次衔接
+fn run_concat<F>(
+    ffmpeg: &Path,
+    list_path: &Path,
+    output: &str,
+    total_duration: f64,
+    audio_filter: Option<&str>,
+    cancelled: &AtomicBool,
+    on_progress: F,
+) -> Result<(), String>
+where
+    F: FnMut(f64),
+{
+    let mut args = vec![
+        "-y".to_string(),
+        "-f".to_string(),
+        "concat".to_string(),
+        "-safe".to_string(),
+        "0".to_string(),
+        "-i".to_string(),
+        list_path.to_string_lossy().to_string(),
+    ];
+    if audio_filter.is_some() {
+        args.extend([
+            "-map".to_string(),
+            "0:v:0".to_string(),
+            "-map".to_string(),
+            audio_stream_map(audio_filter).to_string(),
+        ]);
+    } else {
+        args.extend(["-map".to_string(), "0".to_string()]);
+    }
+    args.extend(["-c".to_string(), "copy".to_string()]);
+    if let Some(filter) = audio_filter {
+        args.extend([
+            "-c:a".to_string(),
+            "aac".to_string(),
+            "-b:a".to_string(),
+            "192k".to_string(),
+            "-af".to_string(),
+            filter.to_string(),
+        ]);
+    }
+    if output_needs_faststart(output) {
+        args.push("-movflags".to_string());
+        args.push("+faststart".to_string());
+    }
 
-        emit_batch_progress(
-            &app,
-            &task_id,
-            "准备批量去片头",
-            0,
-            total,
-            "".into(),
-            0.0,
-            succeeded,
-            skipped,
-            failed,
-        );
+    run_ffmpeg_with_progress(
+        ffmpeg,
+        &args,
+        output,
+        total_duration,
+        cancelled,
+        &FFMPEG_PROCESS,
+        on_progress,
+    )
+}
 
-        for (index, input_path) in paths.iter().enumerate() {
-            if cancelled.load(Ordering::Relaxed) {
-                return Err("操作已取消".to_string());
+/// 逐段截取 `job.range` 内的 `keep_ranges` 后拼接，各段使用同一个音频滤镜
+fn run_segment_cut<F>(
+    app: &AppHandle,
+    job: CutJob,
+    keep_ranges: &[TimeRange],
+    precise_mode: bool,
+    mut on_progress: F,
+) -> Result<(), String>
+where
+    F: FnMut(f64),
+{
+    let CutJob {
+        input,
+        output,
+        cancelled,
+        ..
+    } = job;
+    let work_dir = SegmentWorkDir::create(Path::new(output))?;
+    let input_ext = normalize_video_extension(Path::new(input));
+    let segment_ext = if precise_mode {
+        preferred_precise_output_extension(&input_ext)
+    } else if input_ext.is_empty() {
+        "mp4".into()
+    } else {
+        input_ext
+    };
+
+    let total_duration: f64 = keep_ranges
+        .iter()
+        .map(|range| range.end - range.start)
+        .sum();
+    let mut emit_overall = |completed: f64| {
+        on_progress((completed / total_duration * SEGMENT_PHASE_WEIGHT).clamp(0.0, 100.0));
+    };
+
+    let mut segments = Vec::with_capacity(keep_ranges.len());
+    let mut completed = 0.0;
+    for (index, range) in keep_ranges.iter().enumerate() {
+        if cancelled.load(Ordering::SeqCst) {
+            return Err("操作已取消".to_string());
+        }
+
+        let segment_path = work_dir
+            .path()
+            .join(format!("segment_{:03}.{}", index, segment_ext));
+        let segment = segment_path.to_string_lossy().to_string();
+        let length = range.end - range.start;
+        let segment_job = CutJob {
+            output: &segment,
+            range: *range,
+            ..job
+        };
+        let on_segment_progress = |progress| emit_overall(completed + length * progress / 100.0);
+        if precise_mode {
+            run_precise_cut(app, segment_job, on_segment_progress)?;
+        } else {
+            run_fast_cut(app, segment_job, on_segment_progress)?;
+        }
+        completed += length;
+        emit_overall(completed);
+        segments.push(segment_path);
+    }
+
+    let list_path = work_dir.path().join("segments.txt");
+    write_concat_list(&list_path, &segments)?;
+    run_concat(
+        &get_ffmpeg_path(app),
+        &list_path,
+        output,
+        total_duration,
+        None,
+        cancelled,
+        |progress| {
+            on_progress(SEGMENT_PHASE_WEIGHT + progress * (100.0 - SEGMENT_PHASE_WEIGHT) / 100.0)
+        },
+    )
+    .map_err(|error| {
+        if cancelled.load(Ordering::SeqCst) {
+            error
+        } else {
+            format!("片段拼接失败: {}", error)
+        }
+    })
+}
+
+/// 智能截取中的一段：关键帧之间直接复制，边界处不完整的 GOP 重新编码
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SmartCutPiece {
+    start: f64,
+    end: f64,
+    copy: bool,
+}
+
+/// 与源视频一致的编码参数，重新编码的片段才能与复制的片段无缝拼接
+#[derive(Debug, Deserialize)]
+struct SourceVideoParams {
+    codec_name: Option<String>,
+    profile: Option<String>,
+    pix_fmt: Option<String>,
+    time_base: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SourceVideoProbe {
+    #[serde(default)]
+    streams: Vec<SourceVideoParams>,
+}
+
+/// 第一个视频流的关键帧索引
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyframeIndex {
+    pub path: String,
+    /// 关键帧时间（秒），升序
+    pub keyframes: Vec<f64>,
+    /// 所有帧的显示时间（秒），仅在请求时返回
+    pub frames: Option<Vec<f64>>,
+}
+
+/// 快速截取实际会对齐到的起点，截取开始前通过 `video-cut-snap` 事件上报
+#[derive(Debug, Clone, Serialize)]
+pub struct CutSnapInfo {
+    pub input: String,
+    pub requested_start: f64,
+    pub actual_start: f64,
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct VideoPackets {
+    keyframes: Vec<f64>,
+    frames: Vec<f64>,
+}
+
+/// 缓存的帧索引，文件大小或修改时间变化后失效
+struct CachedVideoPackets {
+    size: u64,
+    modified: Option<SystemTime>,
+    cached_at: Instant,
+    packets: Arc<VideoPackets>,
+}
+
+fn file_signature(path: &str) -> Option<(u64, Option<SystemTime>)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.len(), metadata.modified().ok()))
+}
+
+fn cached_video_packets(path: &str) -> Option<Arc<VideoPackets>> {
+    let (size, modified) = file_signature(path)?;
+    let cache = KEYFRAME_INDEX_CACHE.lock().unwrap();
+    cache
+        .get(path)
+        .filter(|entry| entry.size == size && entry.modified == modified)
+        .map(|entry| entry.packets.clone())
+}
+
+/// 读取整个文件的帧索引，优先使用缓存
+fn load_video_packets(app: &AppHandle, path: &str) -> Result<Arc<VideoPackets>, String> {
+    if let Some(packets) = cached_video_packets(path) {
+        return Ok(packets);
+    }
+
+    let packets = Arc::new(probe_video_packets(app, path, None)?);
+    if let Some((size, modified)) = file_signature(path) {
+        let mut cache = KEYFRAME_INDEX_CACHE.lock().unwrap();
+        if cache.len() >= MAX_KEYFRAME_INDEX_CACHE && !cache.contains_key(path) {
+            let oldest = cache
+                .iter()
+                .min_by_key(|(_, entry)| entry.cached_at)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                cache.remove(&oldest);
             }
+        }
+        cache.insert(
+            path.to_string(),
+            CachedVideoPackets {
+                size,
+                modified,
+                cached_at: Instant::now(),
+                packets: packets.clone(),
+            },
+        );
+    }
+    Ok(packets)
+}
 
-            let current = index + 1;
-            let input = PathBuf::from(input_path);
-            let current_name = input
-                .file_name()
-                .map(|value| value.to_string_lossy().to_string())
-                .unwrap_or_else(|| input_path.clone());
+/// 读取第一个视频流的包信息，`range` 为空时读取整个文件
+fn probe_video_packets(
+    app: &AppHandle,
+    path: &str,
+    range: Option<(f64, f64)>,
+) -> Result<VideoPackets, String> {
+    let mut args = vec![
+        "-v".to_string(),
+        "error".to_string(),
+        "-select_streams".to_string(),
+        "v:0".to_string(),
+        "-show_entries".to_string(),
+        "packet=pts_time,flags".to_string(),
+        "-of".to_string(),
+        "csv=p=0".to_string(),
+    ];
+    if let Some((start, end)) = range {
+        args.push("-read_intervals".to_string());
+        args.push(format!("{}%{}", start.max(0.0), end));
+    }
+    args.push(path.to_string());
 
-            emit_batch_progress(
-                &app,
-                &task_id,
-                "处理中",
-                current,
-                total,
-                current_name.clone(),
-                0.0,
-                succeeded,
-                skipped,
-                failed,
+    let output = Command::new(get_ffprobe_path(app))
+        .args(&args)
+        .output()
+        .map_err(|e| format!("执行 ffprobe 失败: {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("读取关键帧失败: {}", stderr.trim()));
+    }
+    Ok(parse_video_packets(&String::from_utf8_lossy(
+        &output.stdout,
+    )))
+}
+
+/// 解析 `pts_time,flags` 形式的包信息，flags 含 K 的为关键帧
+///
+/// 包按解码顺序输出，排序后即为各帧的显示时间。
+fn parse_video_packets(output: &str) -> VideoPackets {
+    let mut packets = VideoPackets::default();
+    for line in output.lines() {
+        let mut parts = line.trim().split(',');
+        let Some(pts) = parts.next().and_then(|value| value.parse::<f64>().ok()) else {
+            continue;
+        };
+        if !pts.is_finite() {
+            continue;
+        }
+        packets.frames.push(pts);
+        if parts.next().is_some_and(|flags| flags.contains('K')) {
+            packets.keyframes.push(pts);
+        }
+    }
+    for times in [&mut packets.keyframes, &mut packets.frames] {
+        times.sort_by(f64::total_cmp);
+        times.dedup();
+    }
+    packets
+}
+
+/// 读取一段时间内的关键帧，已有缓存时不再调用 ffprobe
+fn keyframes_in_range(
+    app: &AppHandle,
+    path: &str,
+    start: f64,
+    end: f64,
+) -> Result<Vec<f64>, String> {
+    if let Some(packets) = cached_video_packets(path) {
+        return Ok(packets
+            .keyframes
+            .iter()
+            .copied()
+            .filter(|&time| time >= start && time <= end)
+            .collect());
+    }
+    Ok(probe_video_packets(app, path, Some((start, end)))?.keyframes)
+}
+
+/// 不晚于 `start` 的最后一个关键帧，即 `-ss` 放在输入前并直接复制时实际的起点
+fn snap_to_keyframe(keyframes: &[f64], start: f64) -> Option<f64> {
+    keyframes
+        .iter()
+        .rev()
+        .copied()
+        .find(|&time| time <= start + SMART_CUT_TOLERANCE)
+}
+
+fn snapped_cut_start(app: &AppHandle, path: &str, start: f64) -> Result<f64, String> {
+    // 先只读起点前的一小段，GOP 很长时再读取完整索引
+    let window = keyframes_in_range(
+        app,
+        path,
+        (start - SNAP_PROBE_WINDOW).max(0.0),
+        start + SMART_CUT_TOLERANCE,
+    )?;
+    if let Some(time) = snap_to_keyframe(&window, start) {
+        return Ok(time);
+    }
+    let packets = load_video_packets(app, path)?;
+    Ok(snap_to_keyframe(&packets.keyframes, start).unwrap_or(0.0))
+}
+
+/// 获取第一个视频流的关键帧时间，可选同时返回所有帧的时间；结果按文件缓存
+#[tauri::command]
+pub async fn get_keyframe_index(
+    app: AppHandle,
+    path: String,
+    include_frames: Option<bool>,
+) -> Result<KeyframeIndex, String> {
+    tokio::task::spawn_blocking(move || {
+        let packets = load_video_packets(&app, &path)?;
+        debug!(
+            "[关键帧] {}: {} 个关键帧, {} 帧",
+            path,
+            packets.keyframes.len(),
+            packets.frames.len()
+        );
+        Ok(KeyframeIndex {
+            path,
+            keyframes: packets.keyframes.clone(),
+            frames: include_frames
+                .unwrap_or(false)
+                .then(|| packets.frames.clone()),
+        })
+    })
+    .await
+    .map_err(|e| format!("任务执行失败: {}", e))?
+}
+
+/// 规划智能截取：开头到下一个关键帧、最后一个关键帧到结尾重新编码，中间直接复制
+///
+/// 范围内没有可用的关键帧时整段重新编码。
+fn plan_smart_cut(keyframes: &[f64], start: f64, end: f64) -> Vec<SmartCutPiece> {
+    let first_key = keyframes
+        .iter()
+        .copied()
+        .find(|&time| time >= start - SMART_CUT_TOLERANCE);
+    let last_key = keyframes
+        .iter()
+        .rev()
+        .copied()
+        .find(|&time| time <= end + SMART_CUT_TOLERANCE);
+
+    let (first_key, last_key) = match (first_key, last_key) {
+        (Some(first), Some(last)) if last - first > SMART_CUT_TOLERANCE => {
+            (first.max(start), last.min(end))
+        }
+        _ => {
+            return vec![SmartCutPiece {
+                start,
+                end,
+                copy: false,
+            }]
+        }
+    };
+
+    let mut pieces = Vec::with_capacity(3);
+    if first_key - start > SMART_CUT_TOLERANCE {
+        pieces.push(SmartCutPiece {
+            start,
+            end: first_key,
+            copy: false,
+        });
+    }
+    pieces.push(SmartCutPiece {
+        start: first_key,
+        end: last_key,
+        copy: true,
+    });
+    if end - last_key > SMART_CUT_TOLERANCE {
+        pieces.push(SmartCutPiece {
+            start: last_key,
+            end,
+            copy: false,
+        });
+    }
+    pieces
+}
+
+fn probe_source_video_params(app: &AppHandle, path: &str) -> Result<SourceVideoParams, String> {
+    let output = Command::new(get_ffprobe_path(app))
+        .args([
+            "-v",
+            "error",
+            "-select_streams",
+            "v:0",
+            "-show_entries",
+            "stream=codec_name,profile,pix_fmt,time_base",
+            "-of",
+            "json",
+            path,
+        ])
+        .output()
+        .map_err(|e| format!("执行 ffprobe 失败: {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("读取视频流信息失败: {}", stderr.trim()));
+    }
+
+    let probe: SourceVideoProbe =
+        serde_json::from_slice(&output.stdout).map_err(|e| format!("解析视频流信息失败: {}", e))?;
+    probe
+        .streams
+        .into_iter()
+        .next()
+        .ok_or_else(|| "文件中没有视频流".to_string())
+}
+
+/// 智能截取片段的中间格式和复制参数
+///
+/// concat 拼接时只沿用第一个片段的 SPS/PPS 等参数集，而重新编码的片段和复制的片段参数集不同。
+/// H.264/HEVC/MPEG-4 的片段先写成 MPEG-TS，参数集随码流携带，拼接后仍能正确解码。
+fn smart_cut_segment_format(codec: Option<&str>, output: &str) -> (String, Vec<String>) {
+    let mut copy_args = vec!["-c:v".to_string(), "copy".to_string()];
+    let bitstream_filter = match codec {
+        Some("h264") => "h264_mp4toannexb",
+        Some("hevc") => "hevc_mp4toannexb",
+        Some("mpeg4") => "dump_extra",
+        _ => {
+            let ext = Path::new(output)
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_string())
+                .unwrap_or_else(|| "mp4".into());
+            return (ext, copy_args);
+        }
+    };
+    copy_args.extend(["-bsf:v".to_string(), bitstream_filter.to_string()]);
+    ("ts".into(), copy_args)
+}
+
+/// 按源视频编码选择编码器参数，不支持的编码返回 None
+fn smart_cut_encoder_args(params: &SourceVideoParams, segment_ext: &str) -> Option<Vec<String>> {
+    let (encoder, quality_args): (&str, &[&str]) = match params.codec_name.as_deref()? {
+        "h264" => ("libx264", &["-crf", "18", "-preset", "veryfast"]),
+        "hevc" => ("libx265", &["-crf", "20", "-preset", "veryfast"]),
+        "vp9" => ("libvpx-vp9", &["-crf", "24", "-b:v", "0", "-row-mt", "1"]),
+        "mpeg4" => ("mpeg4", &["-q:v", "2"]),
+        _ => return None,
+    };
+
+    let mut args = vec!["-c:v".to_string(), encoder.to_string()];
+    args.extend(quality_args.iter().map(|arg| arg.to_string()));
+    if let Some(profile) = params.profile.as_deref() {
+        // ffprobe 给出的是 "High"、"Main 10" 这样的名称，x264/x265 只接受其中常见的几种
+        let profile = profile.to_ascii_lowercase().replace(' ', "");
+        if matches!(
+            (encoder, profile.as_str()),
+            (
+                "libx264",
+                "baseline" | "main" | "high" | "high10" | "high422" | "high444"
+            ) | ("libx265", "main" | "main10")
+        ) {
+            args.extend(["-profile:v".to_string(), profile]);
+        }
+    }
+    if let Some(pix_fmt) = params.pix_fmt.as_deref() {
+        args.extend(["-pix_fmt".to_string(), pix_fmt.to_string()]);
+    }
+    // 让重新编码的片段与复制的片段使用相同的时间刻度
+    let timescale = params
+        .time_base
+        .as_deref()
+        .and_then(|time_base| time_base.strip_prefix("1/"));
+    if let Some(timescale) = timescale.filter(|_| output_needs_faststart(segment_ext)) {
+        args.extend(["-video_track_timescale".to_string(), timescale.to_string()]);
+    }
+    Some(args)
+}
+
+/// 截取一段，视频按 `video_args` 处理（复制或重新编码），音频始终直接复制
+fn run_smart_cut_piece<F>(
+    ffmpeg: &Path,
+    input: &str,
+    output: &str,
+    piece: &SmartCutPiece,
+    video_args: &[String],
+    cancelled: &AtomicBool,
+    on_progress: F,
+) -> Result<(), String>
+where
+    F: FnMut(f64),
+{
+    let duration = piece.end - piece.start;
+    let mut args = vec![
+        "-y".to_string(),
+        "-ss".to_string(),
+        format!("{}", piece.start),
+        "-i".to_string(),
+        input.to_string(),
+        "-t".to_string(),
+        format!("{}", duration),
+        "-map".to_string(),
+        "0:v:0".to_string(),
+        "-map".to_string(),
+        "0:a?".to_string(),
+        "-map_metadata".to_string(),
+        "0".to_string(),
+    ];
+    args.extend(video_args.iter().cloned());
+    args.extend([
+        "-c:a".to_string(),
+        "copy".to_string(),
+        "-avoid_negative_ts".to_string(),
+        "make_zero".to_string(),
+    ]);
+
+    run_ffmpeg_with_progress(
+        ffmpeg,
+        &args,
+        output,
+        duration,
+        cancelled,
+        &FFMPEG_PROCESS,
+        on_progress,
+    )
+}
+
+/// 设置 `audio_filter` 时拼接阶段重新编码整段音频，其余流直接复制
+fn run_smart_cut<F>(app: &AppHandle, job: CutJob, on_progress: F) -> Result<(), String>
+where
+    F: Fn(f64),
+{
+    let CutJob {
+        input,
+        output,
+        range,
+        audio_filter,
+        cancelled,
+    } = job;
+    let (start_time, end_time) = (range.start, range.end);
+    let params = probe_source_video_params(app, input)?;
+    let (segment_ext, copy_args) = smart_cut_segment_format(params.codec_name.as_deref(), output);
+    let Some(encoder_args) = smart_cut_encoder_args(&params, &segment_ext) else {
+        info!(
+            "[截取] 智能模式不支持 {} 编码，改为精确模式",
+            params.codec_name.as_deref().unwrap_or("未知")
+        );
+        return run_precise_cut(app, job, on_progress);
+    };
+
+    // 多读一小段，保证能找到结尾之前的最后一个关键帧
+    let keyframes = keyframes_in_range(app, input, start_time, end_time + 1.0)?;
+    let pieces = plan_smart_cut(&keyframes, start_time, end_time);
+    debug!("[截取] 智能模式分段: {:?}", pieces);
+
+    let ffmpeg = get_ffmpeg_path(app);
+    let work_dir = SegmentWorkDir::create(Path::new(output))?;
+
+    // 复制比重新编码快得多，按较小的权重计入进度
+    let weight = |piece: &SmartCutPiece| {
+        (piece.end - piece.start)
+            * if piece.copy {
+                SMART_CUT_COPY_WEIGHT
+            } else {
+                1.0
+            }
+    };
+    let total_work: f64 = pieces.iter().map(weight).sum();
+    let emit_overall = |completed: f64| {
+        on_progress((completed / total_work * SEGMENT_PHASE_WEIGHT).clamp(0.0, 100.0));
+    };
+
+    let mut segments = Vec::with_capacity(pieces.len());
+    let mut completed = 0.0;
+    for (index, piece) in pieces.iter().enumerate() {
+        if cancelled.load(Ordering::SeqCst) {
+            return Err("操作已取消".to_string());
+        }
+
+        let segment_path = work_dir
+            .path()
+            .join(format!("part_{:03}.{}", index, segment_ext));
+        let piece_work = weight(piece);
+        run_smart_cut_piece(
+            &ffmpeg,
+            input,
+            &segment_path.to_string_lossy(),
+            piece,
+            if piece.copy {
+                &copy_args
+            } else {
+                &encoder_args
+            },
+            cancelled,
+            |progress| emit_overall(completed + piece_work * progress / 100.0),
+        )
+        .map_err(|error| {
+            if cancelled.load(Ordering::SeqCst) {
+                error
+            } else {
+                format!("视频截取失败: {}", error)
+            }
+        })?;
+        completed += piece_work;
+        segments.push(segment_path);
+    }
+
+    let list_path = work_dir.path().join("segments.txt");
+    write_concat_list(&list_path, &segments)?;
+    run_concat(
+        &ffmpeg,
+        &list_path,
+        output,
+        end_time - start_time,
+        audio_filter,
+        cancelled,
+        |progress| {
+            on_progress(SEGMENT_PHASE_WEIGHT + progress * (100.0 - SEGMENT_PHASE_WEIGHT) / 100.0)
+        },
+    )
+    .map_err(|error| {
+        if cancelled.load(Ordering::SeqCst) {
+            error
+        } else {
+            format!("片段拼接失败: {}", error)
+        }
+    })
+}
+
+/// 按切分点把 `[0, duration]` 分成连续的片段，相距过近的切分点只保留第一个
+fn split_ranges(points: &[f64], duration: f64) -> Vec<TimeRange> {
+    let mut points: Vec<f64> = points
+        .iter()
+        .copied()
+        .filter(|&point| point > MIN_SEGMENT_DURATION && point < duration - MIN_SEGMENT_DURATION)
+        .collect();
+    points.sort_by(f64::total_cmp);
+
+    let mut ranges = Vec::with_capacity(points.len() + 1);
+    let mut start = 0.0;
+    for point in points {
+        if point - start >= MIN_SEGMENT_DURATION {
+            ranges.push(TimeRange { start, end: point });
+            start = point;
+        }
+    }
+    if duration > start {
+        ranges.push(TimeRange {
+            start,
+            end: duration,
+        });
+    }
+    ranges
+}
+
+/// 每隔 `seconds` 取一个切分点
+fn evenly_spaced_points(seconds: f64, duration: f64) -> Result<Vec<f64>, String> {
+    if duration / seconds > MAX_SPLIT_PARTS as f64 {
+        return Err(format!(
+            "拆分后超过 {} 段，请增大每段的时长或大小",
+            MAX_SPLIT_PARTS
+        ));
+    }
+    Ok((1..)
+        .map(|index| index as f64 * seconds)
+        .take_while(|point| *point < duration)
+        .collect())
+}
+
+/// 把升序的切分点对齐到不晚于它的最后一个关键帧；与上一个切分点之间没有关键帧时，
+/// 顺延到上一个切分点之后的第一个关键帧
+fn align_to_keyframes(points: &[f64], keyframes: &[f64]) -> Vec<f64> {
+    if keyframes.is_empty() {
+        return points.to_vec();
+    }
+    let mut aligned: Vec<f64> = Vec::with_capacity(points.len());
+    for &point in points {
+        let previous = aligned.last().copied().unwrap_or(0.0) + MIN_SEGMENT_DURATION;
+        let keyframe = snap_to_keyframe(keyframes, point)
+            .filter(|&time| time > previous)
+            .or_else(|| keyframes.iter().copied().find(|&time| time > previous));
+        if let Some(keyframe) = keyframe {
+            aligned.push(keyframe);
+        }
+    }
+    aligned
+}
+
+/// 依次导出各段；快速模式直接复制，复制失败时改为重新编码
+fn run_split(
+    app: &AppHandle,
+    input: &str,
+    output_dir: Option<&str>,
+    ranges: &[TimeRange],
+    precise_mode: bool,
+    cancelled: &AtomicBool,
+) -> Result<Vec<SplitPart>, String> {
+    let input_path = PathBuf::from(input);
+    let output_dir = match output_dir {
+        Some(dir) => PathBuf::from(dir),
+        None => input_path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| PathBuf::from(".")),
+    };
+    let total_duration: f64 = ranges.iter().map(|range| range.end - range.start).sum();
+    let emit_overall = |done: f64| {
+        let _ = app.emit(
+            "video-progress",
+            (done / total_duration * 100.0).clamp(0.0, 100.0),
+        );
+    };
+
+    let mut parts = Vec::with_capacity(ranges.len());
+    let mut completed = 0.0;
+    for (index, range) in ranges.iter().enumerate() {
+        if cancelled.load(Ordering::SeqCst) {
+            return Err("操作已取消".to_string());
+        }
+
+        let suffix = format!("_part{:03}", index + 1);
+        let length = range.end - range.start;
+        // 输出路径在导出时确定，直接复制失败改为重新编码时扩展名可能不同
+        let job = CutJob {
+            input,
+            output: "",
+            range: *range,
+            audio_filter: None,
+            cancelled,
+        };
+        let run_precise = |output: &str| {
+            run_precise_cut(app, CutJob { output, ..job }, |progress| {
+                emit_overall(completed + length * progress / 100.0)
+            })
+        };
+
+        let mut output = create_unique_output_path(&input_path, &output_dir, &suffix, precise_mode)
+            .to_string_lossy()
+            .to_string();
+        if precise_mode {
+            run_precise(&output)?;
+        } else if let Err(error) = run_fast_cut(
+            app,
+            CutJob {
+                output: &output,
+                ..job
+            },
+            |progress| emit_overall(completed + length * progress / 100.0),
+        ) {
+            if cancelled.load(Ordering::SeqCst) {
+                return Err(error);
+            }
+            info!(
+                "[拆分] 第 {} 段无法直接复制，改为重新编码: {}",
+                index + 1,
+                error
             );
+            let _ = std::fs::remove_file(&output);
+            output = create_unique_output_path(&input_path, &output_dir, &suffix, true)
+                .to_string_lossy()
+                .to_string();
+            run_precise(&output)?;
+        }
+        completed += length;
+        emit_overall(completed);
+
+        let size = std::fs::metadata(&output)
+            .map_err(|e| format!("输出文件不存在: {}", e))?
+            .len();
+        let duration = get_video_duration(app.clone(), output.clone()).unwrap_or(length);
+        parts.push(SplitPart {
+            path: output,
+            start: range.start,
+            duration,
+            size,
+        });
+    }
+    Ok(parts)
+}
 
-            let duration = match get_video_duration(app.clone(), input_path.clone()) {
-                Ok(duration) => duration,
-                Err(error) => {
-                    failed += 1;
-                    items.push(BatchTrimItemResult {
-                        input_path: input_path.clone(),
-                        output_path: None,
-                        status: "failed".into(),
-                        message: error,
-                    });
-                    emit_batch_progress(
-                        &app,
-                        &task_id,
-                        "处理中",
-                        current,
-                        total,
-                        current_name,
-                        100.0,
-                        succeeded,
-                        skipped,
-                        failed,
-                    );
+#[tauri::command]
+pub fn collect_batch_video_files(inputs: Vec<String>) -> Result<Vec<BatchVideoFile>, String> {
+    if inputs.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut seen = HashSet::new();
+    let mut items = Vec::new();
+
+    for input in inputs {
+        let path = PathBuf::from(&input);
+        let metadata = match std::fs::metadata(&path) {
+            Ok(metadata) => metadata,
+            Err(_) => continue,
+        };
+
+        if metadata.is_dir() {
+            for entry in WalkDir::new(&path).into_iter().filter_map(Result::ok) {
+                if !entry.file_type().is_file() || !is_supported_video_path(entry.path()) {
+                    continue;
+                }
+                let entry_path = entry.path().to_string_lossy().to_string();
+                if !seen.insert(entry_path.clone()) {
                     continue;
                 }
+                let entry_meta = match entry.metadata() {
+                    Ok(meta) => meta,
+                    Err(_) => continue,
+                };
+                items.push(BatchVideoFile {
+                    name: entry
+                        .path()
+                        .file_name()
+                        .map(|value| value.to_string_lossy().to_string())
+                        .unwrap_or_default(),
+                    path: entry_path,
+                    size: entry_meta.len(),
+                });
+            }
+        } else if metadata.is_file() && is_supported_video_path(&path) {
+            let normalized = path.to_string_lossy().to_string();
+            if seen.insert(normalized.clone()) {
+                items.push(BatchVideoFile {
+                    name: path
+                        .file_name()
+                        .map(|value| value.to_string_lossy().to_string())
+                        .unwrap_or_default(),
+                    path: normalized,
+                    size: metadata.len(),
+                });
+            }
+        }
+    }
+
+    items.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(items)
+}
+
+/// 批量截取的选项，各文件可以通过 `overrides` 单独设置片头片尾和保留范围
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct BatchTrimOptions {
+    /// 删除的片头时长（秒）
+    pub trim_start: f64,
+    /// 删除的片尾时长（秒），按各文件自身时长计算
+    pub trim_end: f64,
+    /// 只保留的时间段，优先于片头片尾
+    pub keep_range: Option<TimeRange>,
+    pub overrides: Vec<BatchTrimOverride>,
+    pub remove_silence: Option<SilenceOptions>,
+    pub auto_trim: Option<BlackFreezeOptions>,
+    pub normalize_loudness: Option<LoudnessTarget>,
+    pub precise_mode: bool,
+}
+
+impl BatchTrimItemResult {
+    fn new(input_path: &str, status: &str, message: impl Into<String>) -> Self {
+        Self {
+            input_path: input_path.to_string(),
+            output_path: None,
+            status: status.into(),
+            message: message.into(),
+            auto_trim: None,
+            loudness: None,
+        }
+    }
+}
+
+/// 处理批量任务中的一个文件，`on_progress` 接收阶段名称和该文件的进度
+///
+/// 单个文件失败或跳过时返回对应的结果，只有取消时返回 `Err` 结束整个任务。
+fn trim_batch_item<F>(
+    app: &AppHandle,
+    input_path: &str,
+    output: &str,
+    spec: &BatchTrimSpec,
+    precise_mode: bool,
+    cancelled: &AtomicBool,
+    on_progress: F,
+) -> Result<BatchTrimItemResult, String>
+where
+    F: Fn(&str, f64),
+{
+    let failed = |error: String, auto_trim: Option<AutoTrimReport>| {
+        if cancelled.load(Ordering::SeqCst) {
+            Err("操作已取消".to_string())
+        } else {
+            Ok(BatchTrimItemResult {
+                auto_trim,
+                ..BatchTrimItemResult::new(input_path, "failed", error)
+            })
+        }
+    };
+
+    let duration = match get_video_duration(app.clone(), input_path.to_string()) {
+        Ok(duration) => duration,
+        Err(error) => return failed(error, None),
+    };
+    let (start_time, end_time) = match spec.resolve(duration) {
+        Ok(range) => range,
+        Err(message) => return Ok(BatchTrimItemResult::new(input_path, "skipped", message)),
+    };
+
+    // 每完成一个检测阶段，后续进度从这里开始
+    let mut item_offset = 0.0;
+    let mut auto_trim = None;
+    let (start_time, end_time) = match spec.auto_trim {
+        Some(options) => {
+            let detected = detect_black_freeze(
+                &get_ffmpeg_path(app),
+                input_path,
+                &options,
+                end_time,
+                cancelled,
+                &FFMPEG_PROCESS,
+                |progress| on_progress("检测黑屏/静止画面", progress * DETECT_PHASE_WEIGHT / 100.0),
+            );
+            let intervals = match detected {
+                Ok(intervals) => intervals,
+                Err(error) => return failed(error, None),
+            };
+            item_offset += DETECT_PHASE_WEIGHT;
+            let (trimmed_start, trimmed_end) = intervals.trimmed_range(start_time, end_time);
+            auto_trim = Some(AutoTrimReport {
+                intervals,
+                start: trimmed_start,
+                end: trimmed_end,
+            });
+            (trimmed_start, trimmed_end)
+        }
+        None => (start_time, end_time),
+    };
+    if end_time - start_time < MIN_SEGMENT_DURATION {
+        return Ok(BatchTrimItemResult {
+            auto_trim,
+            ..BatchTrimItemResult::new(input_path, "skipped", "去除黑屏和静止画面后没有剩余内容")
+        });
+    }
+
+    let keep_ranges = match spec.remove_silence {
+        Some(silence) => {
+            let detected = detect_silence(
+                &get_ffmpeg_path(app),
+                input_path,
+                &silence,
+                end_time,
+                cancelled,
+                &FFMPEG_PROCESS,
+                |progress| {
+                    on_progress(
+                        "检测静音",
+                        item_offset + progress * DETECT_PHASE_WEIGHT / 100.0,
+                    )
+                },
+            );
+            let silences = match detected {
+                Ok(silences) => silences,
+                Err(error) => return failed(error, auto_trim),
             };
+            item_offset += DETECT_PHASE_WEIGHT;
+            Some(ranges_without(
+                &silences,
+                start_time,
+                end_time,
+                silence.padding,
+            ))
+        }
+        None => None,
+    };
+    if keep_ranges.as_ref().is_some_and(Vec::is_empty) {
+        return Ok(BatchTrimItemResult {
+            auto_trim,
+            ..BatchTrimItemResult::new(input_path, "skipped", "去除静音后没有剩余内容")
+        });
+    }
 
-            if duration <= trim_start + 0.001 {
-                skipped += 1;
-                items.push(BatchTrimItemResult {
-                    input_path: input_path.clone(),
-                    output_path: None,
-                    status: "skipped".into(),
-                    message: format!("视频时长 {:.1}s 不足以删除前 {:.1}s", duration, trim_start),
-                });
+    let loudness_span = TimeRange {
+        start: keep_ranges
+            .as_ref()
+            .and_then(|ranges| ranges.first())
+            .map_or(start_time, |range| range.start),
+        end: keep_ranges
+            .as_ref()
+            .and_then(|ranges| ranges.last())
+            .map_or(end_time, |range| range.end),
+    };
+    let normalization = prepare_cut_normalization(
+        app,
+        input_path,
+        spec.normalize_loudness.as_ref(),
+        loudness_span,
+        cancelled,
+        |progress| {
+            on_progress(
+                "测量响度",
+                item_offset + progress * DETECT_PHASE_WEIGHT / 100.0,
+            )
+        },
+    );
+    let (loudness, audio_filter) = match normalization {
+        Ok(Some((measurement, filter))) => {
+            item_offset += DETECT_PHASE_WEIGHT;
+            (Some(measurement), Some(filter))
+        }
+        Ok(None) => (None, None),
+        Err(error) => return failed(error, auto_trim),
+    };
+
+    let job = CutJob {
+        input: input_path,
+        output,
+        range: TimeRange {
+            start: start_time,
+            end: end_time,
+        },
+        audio_filter: audio_filter.as_deref(),
+        cancelled,
+    };
+    let on_cut_progress = |progress| {
+        on_progress(
+            "处理中",
+            item_offset + progress * (100.0 - item_offset) / 100.0,
+        )
+    };
+    let result = if let Some(keep_ranges) = &keep_ranges {
+        run_segment_cut(app, job, keep_ranges, precise_mode, on_cut_progress)
+    } else if precise_mode {
+        run_precise_cut(app, job, on_cut_progress)
+    } else {
+        run_fast_cut(app, job, on_cut_progress)
+    };
+
+    let outcome = match result {
+        Ok(()) => BatchTrimItemResult::new(input_path, "success", "处理完成"),
+        Err(error) => failed(error, None)?,
+    };
+    Ok(BatchTrimItemResult {
+        output_path: Some(output.to_string()),
+        auto_trim,
+        loudness,
+        ..outcome
+    })
+}
+
+/// 批量截取视频：删除片头、片尾（按各文件自身时长计算），或只保留固定时间段，
+/// 具体见 [`BatchTrimOptions`]；设置 `remove_silence` 时再去掉保留范围内的静音，
+/// 设置 `auto_trim` 时按各文件的检测结果去掉开头结尾的黑屏/静止画面，
+/// 设置 `normalize_loudness` 时按保留范围的响度标准化音频
+#[tauri::command]
+pub async fn batch_trim_videos(
+    app: AppHandle,
+    task_id: String,
+    paths: Vec<String>,
+    options: BatchTrimOptions,
+    output_mode: BatchVideoOutputMode,
+    output_dir: Option<String>,
+    suffix: Option<String>,
+) -> Result<BatchTrimResult, String> {
+    if paths.is_empty() {
+        return Err("请先选择要处理的视频".into());
+    }
+
+    let default_spec = BatchTrimSpec {
+        trim_start: options.trim_start,
+        trim_end: options.trim_end,
+        keep_range: options.keep_range,
+        remove_silence: options.remove_silence,
+        auto_trim: options.auto_trim,
+        normalize_loudness: options.normalize_loudness,
+    };
+    let overrides: HashMap<&str, &BatchTrimOverride> = options
+        .overrides
+        .iter()
+        .map(|item| (item.path.as_str(), item))
+        .collect();
+    let specs = paths
+        .iter()
+        .map(|path| {
+            let spec = overrides
+                .get(path.as_str())
+                .map_or(default_spec, |item| default_spec.with_override(item));
+            spec.validate().map(|_| spec)
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    let precise_mode = options.precise_mode;
+
+    let cancelled = register_batch_task(&task_id);
+    let task_id_for_cleanup = task_id.clone();
+    let suffix = suffix.unwrap_or_else(|| "_trim".into());
+
+    let task_result = tokio::task::spawn_blocking(move || {
+        let total = paths.len();
+        let target_directory = output_dir.map(PathBuf::from);
+        let mut succeeded = 0usize;
+        let mut skipped = 0usize;
+        let mut failed = 0usize;
+        let mut items = Vec::with_capacity(total);
+
+        emit_batch_progress(
+            &app,
+            &task_id,
+            "准备批量去片头",
+            0,
+            total,
+            "".into(),
+            0.0,
+            succeeded,
+            skipped,
+            failed,
+        );
+
+        for (index, (input_path, spec)) in paths.iter().zip(&specs).enumerate() {
+            if cancelled.load(Ordering::Relaxed) {
+                return Err("操作已取消".to_string());
+            }
+
+            let current = index + 1;
+            let input = PathBuf::from(input_path);
+            let current_name = input
+                .file_name()
+                .map(|value| value.to_string_lossy().to_string())
+                .unwrap_or_else(|| input_path.clone());
+            let emit_item_progress = |stage: &str, item_progress: f64| {
                 emit_batch_progress(
                     &app,
                     &task_id,
-                    "处理中",
+                    stage,
                     current,
                     total,
-                    current_name,
-                    100.0,
+                    current_name.clone(),
+                    item_progress,
                     succeeded,
                     skipped,
                     failed,
                 );
-                continue;
-            }
+            };
+            emit_item_progress("处理中", 0.0);
 
             let output_parent = match output_mode {
                 BatchVideoOutputMode::Source => input
@@ -826,61 +2137,23 @@ pub async fn batch_trim_videos(
                     .clone()
                     .ok_or_else(|| "请先选择输出目录".to_string())?,
             };
+            let output = create_unique_output_path(&input, &output_parent, &suffix, precise_mode);
 
-            let output_path =
-                create_unique_output_path(&input, &output_parent, &suffix, precise_mode);
-            let output_string = output_path.to_string_lossy().to_string();
-
-            let result = if precise_mode {
-                run_precise_cut(
-                    &app,
-                    input_path,
-                    &output_string,
-                    trim_start,
-                    duration,
-                    &cancelled,
-                    |item_progress| {
-                        emit_batch_progress(
-                            &app,
-                            &task_id,
-                            "处理中",
-                            current,
-                            total,
-                            current_name.clone(),
-                            item_progress,
-                            succeeded,
-                            skipped,
-                            failed,
-                        );
-                    },
-                )
-            } else {
-                run_fast_cut(&app, input_path, &output_string, trim_start, duration)
-            };
-
-            match result {
-                Ok(()) => {
-                    succeeded += 1;
-                    items.push(BatchTrimItemResult {
-                        input_path: input_path.clone(),
-                        output_path: Some(output_string.clone()),
-                        status: "success".into(),
-                        message: "处理完成".into(),
-                    });
-                }
-                Err(error) if error.contains("取消") => {
-                    return Err("操作已取消".into());
-                }
-                Err(error) => {
-                    failed += 1;
-                    items.push(BatchTrimItemResult {
-                        input_path: input_path.clone(),
-                        output_path: Some(output_string),
-                        status: "failed".into(),
-                        message: error,
-                    });
-                }
+            let item = trim_batch_item(
+                &app,
+                input_path,
+                &output.to_string_lossy(),
+                spec,
+                precise_mode,
+                &cancelled,
+                emit_item_progress,
+            )?;
+            match item.status.as_str() {
+                "success" => succeeded += 1,
+                "skipped" => skipped += 1,
+                _ => failed += 1,
             }
+            items.push(item);
 
             emit_batch_progress(
                 &app,
@@ -909,233 +2182,661 @@ pub async fn batch_trim_videos(
             failed,
         );
 
-        Ok::<BatchTrimResult, String>(BatchTrimResult {
-            total,
-            succeeded,
-            skipped,
-            failed,
-            items,
-        })
-    })
-    .await;
+        Ok::<BatchTrimResult, String>(BatchTrimResult {
+            total,
+            succeeded,
+            skipped,
+            failed,
+            items,
+        })
+    })
+    .await;
+
+    cleanup_batch_task(&task_id_for_cleanup);
+
+    task_result.map_err(|error| format!("任务执行失败: {}", error))?
+}
+
+/// 截取视频（快速模式）
+///
+/// 设置 `normalize_loudness` 时先测量截取范围内的响度，再按两遍标准化重新编码音频，
+/// 视频仍直接复制。进度通过 `video-progress` 事件上报，使用 `cancel_video_cut` 取消。
+#[tauri::command]
+pub async fn cut_video(
+    app: AppHandle,
+    input: String,
+    output: String,
+    start_time: f64,
+    end_time: f64,
+    normalize_loudness: Option<LoudnessTarget>,
+) -> Result<String, String> {
+    if end_time <= start_time {
+        return Err("结束时间必须大于开始时间".into());
+    }
+
+    VIDEO_CANCELLED.store(false, Ordering::SeqCst);
+    info!(
+        "[截取] 快速模式: {} -> {}, {:.2}s - {:.2}s (时长 {:.2}s)",
+        input,
+        output,
+        start_time,
+        end_time,
+        end_time - start_time
+    );
+
+    let output_for_task = output.clone();
+    let cancelled = VIDEO_CANCELLED.clone();
+    tokio::task::spawn_blocking(move || {
+        // 直接复制只能从关键帧开始，提前告知前端实际的起点
+        match snapped_cut_start(&app, &input, start_time) {
+            Ok(actual_start) => {
+                info!("[截取] 起点对齐到关键帧: {:.3}s", actual_start);
+                let _ = app.emit(
+                    "video-cut-snap",
+                    CutSnapInfo {
+                        input: input.clone(),
+                        requested_start: start_time,
+                        actual_start,
+                    },
+                );
+            }
+            Err(error) => debug!("[截取] 无法确定关键帧对齐点: {}", error),
+        }
+
+        let normalization = prepare_cut_normalization(
+            &app,
+            &input,
+            normalize_loudness.as_ref(),
+            TimeRange {
+                start: start_time,
+                end: end_time,
+            },
+            &cancelled,
+            |progress| {
+                let _ = app.emit("video-progress", progress * MEASURE_PHASE_WEIGHT / 100.0);
+            },
+        )?;
+        let offset = if normalization.is_some() {
+            MEASURE_PHASE_WEIGHT
+        } else {
+            0.0
+        };
 
-    cleanup_batch_task(&task_id_for_cleanup);
+        run_fast_cut(
+            &app,
+            CutJob {
+                input: &input,
+                output: &output_for_task,
+                range: TimeRange {
+                    start: start_time,
+                    end: end_time,
+                },
+                audio_filter: normalization.as_ref().map(|(_, filter)| filter.as_str()),
+                cancelled: &cancelled,
+            },
+            |progress| {
+                let _ = app.emit(
+                    "video-progress",
+                    offset + progress * (100.0 - offset) / 100.0,
+                );
+            },
+        )
+    })
+    .await
+    .map_err(|e| format!("任务执行失败: {}", e))??;
 
-    task_result.map_err(|error| format!("任务执行失败: {}", error))?
+    info!("[截取] 快速模式完成: {}", output);
+    Ok(output)
 }
 
-/// 截取视频（快速模式）
+/// 精确截取视频（重新编码，带进度反馈）
+///
+/// 设置 `normalize_loudness` 时先测量截取范围内的响度，再按两遍标准化输出音频。
 #[tauri::command]
-pub fn cut_video(
+pub async fn cut_video_precise(
     app: AppHandle,
     input: String,
     output: String,
     start_time: f64,
     end_time: f64,
+    normalize_loudness: Option<LoudnessTarget>,
 ) -> Result<String, String> {
     if end_time <= start_time {
         return Err("结束时间必须大于开始时间".into());
     }
 
-    let ffmpeg = get_ffmpeg_path(&app);
-    let duration = end_time - start_time;
-    let use_faststart = output_needs_faststart(&output);
+    VIDEO_CANCELLED.store(false, Ordering::SeqCst);
     info!(
-        "[截取] 快速模式: {} -> {}, {:.2}s - {:.2}s (时长 {:.2}s)",
-        input, output, start_time, end_time, duration
+        "[截取] 精确模式: {} -> {}, {:.2}s - {:.2}s (时长 {:.2}s)",
+        input,
+        output,
+        start_time,
+        end_time,
+        end_time - start_time
     );
 
-    let mut args = vec![
-        "-y".to_string(),
-        "-ss".to_string(),
-        format!("{}", start_time),
-        "-i".to_string(),
-        input.clone(),
-        "-t".to_string(),
-        format!("{}", duration),
-        "-map".to_string(),
-        "0:v:0".to_string(),
-        "-map".to_string(),
-        "0:a?".to_string(),
-        "-map_metadata".to_string(),
-        "0".to_string(),
-        "-c".to_string(),
-        "copy".to_string(),
-        "-avoid_negative_ts".to_string(),
-        "make_zero".to_string(),
-    ];
-
-    if use_faststart {
-        args.push("-movflags".to_string());
-        args.push("+faststart".to_string());
-    }
-
-    args.push(output.clone());
+    let output_for_task = output.clone();
+    let cancelled = VIDEO_CANCELLED.clone();
+    tokio::task::spawn_blocking(move || {
+        let normalization = prepare_cut_normalization(
+            &app,
+            &input,
+            normalize_loudness.as_ref(),
+            TimeRange {
+                start: start_time,
+                end: end_time,
+            },
+            &cancelled,
+            |progress| {
+                let _ = app.emit("video-progress", progress * MEASURE_PHASE_WEIGHT / 100.0);
+            },
+        )?;
+        let offset = if normalization.is_some() {
+            MEASURE_PHASE_WEIGHT
+        } else {
+            0.0
+        };
 
-    let result = Command::new(&ffmpeg)
-        .args(&args)
-        .stdout(Stdio::null())
-        .stderr(Stdio::piped())
-        .output()
-        .map_err(|e| format!("执行 ffmpeg 失败: {}", e))?;
+        run_precise_cut(
+            &app,
+            CutJob {
+                input: &input,
+                output: &output_for_task,
+                range: TimeRange {
+                    start: start_time,
+                    end: end_time,
+                },
+                audio_filter: normalization.as_ref().map(|(_, filter)| filter.as_str()),
+                cancelled: &cancelled,
+            },
+            |progress| {
+                let _ = app.emit(
+                    "video-progress",
+                    offset + progress * (100.0 - offset) / 100.0,
+                );
+            },
+        )
+    })
+    .await
+    .map_err(|e| format!("任务执行失败: {}", e))??;
 
-    if result.status.success() {
-        info!("[截取] 快速模式完成: {}", output);
-        Ok(output)
-    } else {
-        let stderr = String::from_utf8_lossy(&result.stderr).trim().to_string();
-        if stderr.is_empty() {
-            Err("视频截取失败".into())
-        } else {
-            Err(format!("视频截取失败: {}", stderr))
-        }
-    }
+    info!("[截取] 精确模式完成: {}", output);
+    Ok(output)
 }
 
-/// 精确截取视频（重新编码，带进度反馈）
+/// 智能截取：只重新编码切点附近不完整的 GOP，中间部分直接复制，兼顾精确与速度
+///
+/// 重新编码的部分沿用源视频的编码和像素格式；不支持的编码自动改用精确模式。
+/// 设置 `normalize_loudness` 时先测量截取范围内的响度，拼接时整段重新编码音频，视频部分不受影响。
+/// 进度通过 `video-progress` 事件上报，使用 `cancel_video_cut` 取消。
 #[tauri::command]
-pub async fn cut_video_precise(
+pub async fn cut_video_smart(
     app: AppHandle,
     input: String,
     output: String,
     start_time: f64,
     end_time: f64,
+    normalize_loudness: Option<LoudnessTarget>,
 ) -> Result<String, String> {
     if end_time <= start_time {
         return Err("结束时间必须大于开始时间".into());
     }
 
     VIDEO_CANCELLED.store(false, Ordering::SeqCst);
+    let cancelled = VIDEO_CANCELLED.clone();
+    info!(
+        "[截取] 智能模式: {} -> {}, {:.2}s - {:.2}s",
+        input, output, start_time, end_time
+    );
 
-    let ffmpeg = get_ffmpeg_path(&app);
-    let duration = end_time - start_time;
+    let output_for_task = output.clone();
+    tokio::task::spawn_blocking(move || {
+        let normalization = prepare_cut_normalization(
+            &app,
+            &input,
+            normalize_loudness.as_ref(),
+            TimeRange {
+                start: start_time,
+                end: end_time,
+            },
+            &cancelled,
+            |progress| {
+                let _ = app.emit("video-progress", progress * MEASURE_PHASE_WEIGHT / 100.0);
+            },
+        )?;
+        let offset = if normalization.is_some() {
+            MEASURE_PHASE_WEIGHT
+        } else {
+            0.0
+        };
+
+        run_smart_cut(
+            &app,
+            CutJob {
+                input: &input,
+                output: &output_for_task,
+                range: TimeRange {
+                    start: start_time,
+                    end: end_time,
+                },
+                audio_filter: normalization.as_ref().map(|(_, filter)| filter.as_str()),
+                cancelled: &cancelled,
+            },
+            |progress| {
+                let _ = app.emit(
+                    "video-progress",
+                    offset + progress * (100.0 - offset) / 100.0,
+                );
+            },
+        )
+    })
+    .await
+    .map_err(|e| format!("任务执行失败: {}", e))??;
+
+    std::fs::metadata(&output).map_err(|e| format!("输出文件不存在: {}", e))?;
+    info!("[截取] 智能模式完成: {}", output);
+    Ok(output)
+}
+
+/// 多片段截取：保留（或删除）多个时间段，逐段截取后拼接为一个文件
+///
+/// 设置 `normalize_loudness` 时按第一段开头到最后一段结尾的整体响度标准化，各段增益一致。
+/// 进度通过 `video-progress` 事件上报整体百分比，使用 `cancel_video_cut` 取消。
+#[tauri::command]
+pub async fn cut_video_segments(
+    app: AppHandle,
+    input: String,
+    output: String,
+    ranges: Vec<TimeRange>,
+    mode: SegmentMode,
+    precise_mode: bool,
+    normalize_loudness: Option<LoudnessTarget>,
+) -> Result<String, String> {
+    let duration = get_video_duration(app.clone(), input.clone())?;
+    let keep_ranges = resolve_keep_ranges(&ranges, mode, duration)?;
+
+    VIDEO_CANCELLED.store(false, Ordering::SeqCst);
+    let cancelled = VIDEO_CANCELLED.clone();
     info!(
-        "[截取] 精确模式: {} -> {}, {:.2}s - {:.2}s (时长 {:.2}s)",
-        input, output, start_time, end_time, duration
+        "[截取] 多片段{}模式: {} -> {}, 保留 {} 段",
+        if precise_mode { "精确" } else { "快速" },
+        input,
+        output,
+        keep_ranges.len()
     );
 
-    let output_clone = output.clone();
-    let output_for_cleanup = output.clone();
+    let output_for_task = output.clone();
+    tokio::task::spawn_blocking(move || {
+        let span = TimeRange {
+            start: keep_ranges.first().map_or(0.0, |range| range.start),
+            end: keep_ranges.last().map_or(duration, |range| range.end),
+        };
+        let normalization = prepare_cut_normalization(
+            &app,
+            &input,
+            normalize_loudness.as_ref(),
+            span,
+            &cancelled,
+            |progress| {
+                let _ = app.emit("video-progress", progress * MEASURE_PHASE_WEIGHT / 100.0);
+            },
+        )?;
+        let offset = if normalization.is_some() {
+            MEASURE_PHASE_WEIGHT
+        } else {
+            0.0
+        };
+
+        run_segment_cut(
+            &app,
+            CutJob {
+                input: &input,
+                output: &output_for_task,
+                range: span,
+                audio_filter: normalization.as_ref().map(|(_, filter)| filter.as_str()),
+                cancelled: &cancelled,
+            },
+            &keep_ranges,
+            precise_mode,
+            |progress| {
+                let _ = app.emit(
+                    "video-progress",
+                    offset + progress * (100.0 - offset) / 100.0,
+                );
+            },
+        )
+    })
+    .await
+    .map_err(|e| format!("任务执行失败: {}", e))??;
+
+    info!("[截取] 多片段截取完成: {}", output);
+    Ok(output)
+}
+
+/// 检测视频中的静音区间
+///
+/// 进度通过 `video-progress` 事件上报，使用 `cancel_video_cut` 取消。
+#[tauri::command]
+pub async fn detect_silence_intervals(
+    app: AppHandle,
+    input: String,
+    options: Option<SilenceOptions>,
+) -> Result<Vec<TimeRange>, String> {
+    let options = options.unwrap_or_default();
+    let duration = get_video_duration(app.clone(), input.clone())?;
+
+    VIDEO_CANCELLED.store(false, Ordering::SeqCst);
     let cancelled = VIDEO_CANCELLED.clone();
-    let use_faststart = output_needs_faststart(&output);
+    let silences = tokio::task::spawn_blocking(move || {
+        detect_silence(
+            &get_ffmpeg_path(&app),
+            &input,
+            &options,
+            duration,
+            &cancelled,
+            &FFMPEG_PROCESS,
+            |progress| {
+                let _ = app.emit("video-progress", progress);
+            },
+        )
+    })
+    .await
+    .map_err(|e| format!("任务执行失败: {}", e))??;
 
-    let result = tokio::task::spawn_blocking(move || {
-        let mut args = vec![
-            "-y".to_string(),
-            "-i".to_string(),
-            input,
-            "-ss".to_string(),
-            format!("{}", start_time),
-            "-t".to_string(),
-            format!("{}", duration),
-            "-map".to_string(),
-            "0:v:0".to_string(),
-            "-map".to_string(),
-            "0:a?".to_string(),
-            "-map_metadata".to_string(),
-            "0".to_string(),
-            "-c:v".to_string(),
-            "libx264".to_string(),
-            "-crf".to_string(),
-            "23".to_string(),
-            "-preset".to_string(),
-            "veryfast".to_string(),
-            "-pix_fmt".to_string(),
-            "yuv420p".to_string(),
-            "-c:a".to_string(),
-            "aac".to_string(),
-            "-b:a".to_string(),
-            "128k".to_string(),
-            "-avoid_negative_ts".to_string(),
-            "make_zero".to_string(),
-        ];
-
-        if use_faststart {
-            args.push("-movflags".to_string());
-            args.push("+faststart".to_string());
+    info!("[静音] 检测到 {} 段静音", silences.len());
+    Ok(silences)
+}
+
+/// 导出去掉静音后的视频，静音两侧保留 `padding` 秒
+///
+/// 进度通过 `video-progress` 事件上报，使用 `cancel_video_cut` 取消。
+#[tauri::command]
+pub async fn remove_silence(
+    app: AppHandle,
+    input: String,
+    output: String,
+    options: Option<SilenceOptions>,
+    precise_mode: bool,
+) -> Result<String, String> {
+    let options = options.unwrap_or_default();
+    options.validate()?;
+    let duration = get_video_duration(app.clone(), input.clone())?;
+
+    VIDEO_CANCELLED.store(false, Ordering::SeqCst);
+    let cancelled = VIDEO_CANCELLED.clone();
+    info!("[静音] 去除静音: {} -> {}", input, output);
+
+    let output_for_task = output.clone();
+    tokio::task::spawn_blocking(move || {
+        let silences = detect_silence(
+            &get_ffmpeg_path(&app),
+            &input,
+            &options,
+            duration,
+            &cancelled,
+            &FFMPEG_PROCESS,
+            |progress| {
+                let _ = app.emit("video-progress", progress * DETECT_PHASE_WEIGHT / 100.0);
+            },
+        )?;
+        let keep_ranges = ranges_without(&silences, 0.0, duration, options.padding);
+        if keep_ranges.is_empty() {
+            return Err("去除静音后没有剩余内容".to_string());
         }
+        info!(
+            "[静音] 检测到 {} 段静音，保留 {} 段",
+            silences.len(),
+            keep_ranges.len()
+        );
 
-        args.extend([
-            "-progress".to_string(),
-            "pipe:1".to_string(),
-            output_clone.clone(),
-        ]);
+        run_segment_cut(
+            &app,
+            CutJob {
+                input: &input,
+                output: &output_for_task,
+                range: TimeRange {
+                    start: 0.0,
+                    end: duration,
+                },
+                audio_filter: None,
+                cancelled: &cancelled,
+            },
+            &keep_ranges,
+            precise_mode,
+            |progress| {
+                let overall =
+                    DETECT_PHASE_WEIGHT + progress * (100.0 - DETECT_PHASE_WEIGHT) / 100.0;
+                let _ = app.emit("video-progress", overall);
+            },
+        )
+    })
+    .await
+    .map_err(|e| format!("任务执行失败: {}", e))??;
 
-        let mut child = Command::new(&ffmpeg)
-            .args(&args)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::null())
-            .spawn()
-            .map_err(|e| format!("启动 ffmpeg 失败: {}", e))?;
-
-        let pid = child.id();
-        *FFMPEG_PROCESS.lock().unwrap() = Some(pid);
-
-        let cut_result = (|| -> Result<bool, String> {
-            let stdout = child
-                .stdout
-                .take()
-                .ok_or_else(|| "无法读取 ffmpeg 输出".to_string())?;
-            let reader = BufReader::new(stdout);
-
-            for line in reader.lines().map_while(Result::ok) {
-                if cancelled.load(Ordering::SeqCst) {
-                    info!("[截取] 用户取消操作，终止 FFmpeg 进程");
-                    let _ = child.kill();
-                    break;
-                }
+    info!("[静音] 去除静音完成: {}", output);
+    Ok(output)
+}
 
-                if let Some(time_str) = line.strip_prefix("out_time_ms=") {
-                    if let Ok(ms) = time_str.parse::<i64>() {
-                        let current = ms as f64 / 1_000_000.0;
-                        let progress = (current / duration * 100.0).min(100.0).max(0.0);
-                        let _ = app.emit("video-progress", progress);
-                    }
-                } else if let Some(time_str) = line.strip_prefix("out_time_us=") {
-                    if let Ok(us) = time_str.parse::<i64>() {
-                        let current = us as f64 / 1_000_000.0;
-                        let progress = (current / duration * 100.0).min(100.0).max(0.0);
-                        let _ = app.emit("video-progress", progress);
-                    }
-                } else if let Some(time_str) = line.strip_prefix("out_time=") {
-                    if let Some(secs) = parse_ffmpeg_time(time_str) {
-                        let progress = (secs / duration * 100.0).min(100.0).max(0.0);
-                        let _ = app.emit("video-progress", progress);
-                    }
-                }
-            }
+/// 检测黑屏和静止画面的区间
+///
+/// 进度通过 `video-progress` 事件上报，使用 `cancel_video_cut` 取消。
+#[tauri::command]
+pub async fn detect_black_frozen_frames(
+    app: AppHandle,
+    input: String,
+    options: Option<BlackFreezeOptions>,
+) -> Result<BlackFreezeIntervals, String> {
+    let options = options.unwrap_or_default();
+    let duration = get_video_duration(app.clone(), input.clone())?;
+
+    VIDEO_CANCELLED.store(false, Ordering::SeqCst);
+    let cancelled = VIDEO_CANCELLED.clone();
+    let intervals = tokio::task::spawn_blocking(move || {
+        detect_black_freeze(
+            &get_ffmpeg_path(&app),
+            &input,
+            &options,
+            duration,
+            &cancelled,
+            &FFMPEG_PROCESS,
+            |progress| {
+                let _ = app.emit("video-progress", progress);
+            },
+        )
+    })
+    .await
+    .map_err(|e| format!("任务执行失败: {}", e))??;
+
+    info!(
+        "[黑屏检测] 黑屏 {} 段, 静止 {} 段",
+        intervals.black.len(),
+        intervals.frozen.len()
+    );
+    Ok(intervals)
+}
 
-            let status = child
-                .wait()
-                .map_err(|e| format!("等待 ffmpeg 失败: {}", e))?;
+/// 检测场景切换，返回切换时间、分数和缩略图，可作为拆分点或截取的参考边界
+///
+/// `threshold` 为 0-1 的灵敏度，越小检测到的切换越多，默认 0.4。
+/// 进度通过 `video-progress` 事件上报，使用 `cancel_video_cut` 取消。
+#[tauri::command]
+pub async fn detect_scene_changes(
+    app: AppHandle,
+    input: String,
+    threshold: Option<f64>,
+) -> Result<Vec<SceneChange>, String> {
+    let threshold = threshold.unwrap_or(DEFAULT_SCENE_THRESHOLD);
+    let duration = get_video_duration(app.clone(), input.clone())?;
 
+    VIDEO_CANCELLED.store(false, Ordering::SeqCst);
+    let cancelled = VIDEO_CANCELLED.clone();
+    let scenes = tokio::task::spawn_blocking(move || {
+        let mut scenes = detect_scenes(
+            &get_ffmpeg_path(&app),
+            &input,
+            threshold,
+            duration,
+            &cancelled,
+            &FFMPEG_PROCESS,
+            |progress| {
+                let _ = app.emit("video-progress", progress * SCENE_DETECT_WEIGHT / 100.0);
+            },
+        )?;
+
+        let thumbnail_count = scenes.len().min(MAX_SCENE_THUMBNAILS);
+        for (index, scene) in scenes.iter_mut().take(thumbnail_count).enumerate() {
             if cancelled.load(Ordering::SeqCst) {
-                let _ = std::fs::remove_file(&output_clone);
                 return Err("操作已取消".to_string());
             }
+            // 普通定位可能落在上一个场景的关键帧上，这里必须精确定位
+            scene.thumbnail =
+                generate_preview_frame_with_options(&app, &input, scene.time, Some(360), 6, true)
+                    .ok();
+            let progress = SCENE_DETECT_WEIGHT
+                + (index + 1) as f64 / thumbnail_count as f64 * (100.0 - SCENE_DETECT_WEIGHT);
+            let _ = app.emit("video-progress", progress);
+        }
+        Ok(scenes)
+    })
+    .await
+    .map_err(|e| format!("任务执行失败: {}", e))??;
+
+    info!("[场景] 检测到 {} 处场景切换", scenes.len());
+    Ok(scenes)
+}
+
+/// 按时间点把视频拆分成多个文件，例如每个场景一个文件
+///
+/// 文件名为 `原名_part001` 依次编号，`output_dir` 为空时放在原视频所在目录。
+/// 进度通过 `video-progress` 事件上报，使用 `cancel_video_cut` 取消。
+#[tauri::command]
+pub async fn split_video_at_points(
+    app: AppHandle,
+    input: String,
+    points: Vec<f64>,
+    output_dir: Option<String>,
+    precise_mode: bool,
+) -> Result<Vec<SplitPart>, String> {
+    VIDEO_CANCELLED.store(false, Ordering::SeqCst);
+    let cancelled = VIDEO_CANCELLED.clone();
 
-            if !status.success() {
-                let _ = std::fs::remove_file(&output_clone);
-                return Err("视频截取失败".into());
+    let parts = tokio::task::spawn_blocking(move || {
+        let duration = get_video_duration(app.clone(), input.clone())?;
+        let mut points = points;
+        points.sort_by(f64::total_cmp);
+        // 快速模式直接复制，只能从关键帧开始，否则每段开头会重复上一段的结尾
+        if !precise_mode {
+            match load_video_packets(&app, &input) {
+                Ok(packets) => points = align_to_keyframes(&points, &packets.keyframes),
+                Err(error) => debug!("[拆分] 无法读取关键帧，按原切分点拆分: {}", error),
             }
+        }
+        let ranges = split_ranges(&points, duration);
+        if ranges.len() < 2 {
+            return Err("没有有效的拆分点".to_string());
+        }
+        info!("[拆分] {} -> {} 段", input, ranges.len());
+        run_split(
+            &app,
+            &input,
+            output_dir.as_deref(),
+            &ranges,
+            precise_mode,
+            &cancelled,
+        )
+    })
+    .await
+    .map_err(|e| format!("任务执行失败: {}", e))??;
+
+    info!("[拆分] 完成，共 {} 个文件", parts.len());
+    Ok(parts)
+}
 
-            let _ = app.emit("video-progress", 100.0);
-            Ok(true)
-        })();
+/// 按时长、大小或章节拆分视频，尽量直接复制流，切分点对齐到关键帧
+///
+/// 按时长拆分时 `segment_seconds` 为每段秒数；按大小拆分时 `segment_megabytes`
+/// 为每段的大致大小（MB），按平均码率换算成时长。
+/// 进度通过 `video-progress` 事件上报，使用 `cancel_video_cut` 取消。
+#[tauri::command]
+pub async fn split_video(
+    app: AppHandle,
+    input: String,
+    mode: SplitMode,
+    segment_seconds: Option<f64>,
+    segment_megabytes: Option<f64>,
+    output_dir: Option<String>,
+) -> Result<Vec<SplitPart>, String> {
+    VIDEO_CANCELLED.store(false, Ordering::SeqCst);
+    let cancelled = VIDEO_CANCELLED.clone();
+    info!("[拆分] {} 按{:?}拆分", input, mode);
+
+    let parts = tokio::task::spawn_blocking(move || {
+        let info = probe_media_info(&app, &input)?;
+        let duration = info
+            .container
+            .duration
+            .ok_or_else(|| "解析时长失败: 无法读取时长".to_string())?;
+
+        let points = match mode {
+            SplitMode::Duration => {
+                let seconds = segment_seconds
+                    .filter(|seconds| *seconds > 0.0)
+                    .ok_or_else(|| "请设置每段的时长".to_string())?;
+                evenly_spaced_points(seconds, duration)?
+            }
+            SplitMode::Size => {
+                let megabytes = segment_megabytes
+                    .filter(|megabytes| *megabytes > 0.0)
+                    .ok_or_else(|| "请设置每段的大小".to_string())?;
+                let size = info
+                    .container
+                    .size
+                    .or_else(|| std::fs::metadata(&input).ok().map(|m| m.len()))
+                    .ok_or_else(|| "无法读取文件大小".to_string())?;
+                let bytes_per_second = size as f64 / duration;
+                let seconds = megabytes * 1024.0 * 1024.0 / bytes_per_second * SPLIT_SIZE_MARGIN;
+                evenly_spaced_points(seconds, duration)?
+            }
+            SplitMode::Chapters => {
+                let points: Vec<f64> = info
+                    .chapters
+                    .iter()
+                    .map(|chapter| chapter.start)
+                    .filter(|start| *start > 0.0)
+                    .collect();
+                if points.is_empty() {
+                    return Err("视频没有章节信息".to_string());
+                }
+                points
+            }
+        };
 
-        *FFMPEG_PROCESS.lock().unwrap() = None;
-        cut_result
+        // 直接复制只能从关键帧开始，切分点对齐后各段才能首尾相接
+        let points = match load_video_packets(&app, &input) {
+            Ok(packets) => align_to_keyframes(&points, &packets.keyframes),
+            Err(error) => {
+                debug!("[拆分] 无法读取关键帧，按原切分点拆分: {}", error);
+                points
+            }
+        };
+        let ranges = split_ranges(&points, duration);
+        if ranges.len() < 2 {
+            return Err("视频已经小于每段的时长或大小，无需拆分".to_string());
+        }
+        run_split(
+            &app,
+            &input,
+            output_dir.as_deref(),
+            &ranges,
+            false,
+            &cancelled,
+        )
     })
     .await
     .map_err(|e| format!("任务执行失败: {}", e))??;
 
-    if result {
-        info!("[截取] 精确模式完成: {}", output);
-        Ok(output)
-    } else {
-        let _ = std::fs::remove_file(&output_for_cleanup);
-        Err("视频截取失败".into())
-    }
+    info!("[拆分] 完成，共 {} 个文件", parts.len());
+    Ok(parts)
 }
 
 /// 取消视频截取操作
@@ -1177,18 +2878,6 @@ pub fn cancel_batch_video_trim(task_id: String) {
     }
 }
 
-fn parse_ffmpeg_time(time_str: &str) -> Option<f64> {
-    let parts: Vec<&str> = time_str.split(':').collect();
-    if parts.len() == 3 {
-        let hours: f64 = parts[0].parse().ok()?;
-        let minutes: f64 = parts[1].parse().ok()?;
-        let seconds: f64 = parts[2].parse().ok()?;
-        Some(hours * 3600.0 + minutes * 60.0 + seconds)
-    } else {
-        None
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1246,6 +2935,251 @@ mod tests {
         assert!(result.iter().any(|item| item.name == "b.MOV"));
     }
 
+    fn ranges(values: &[(f64, f64)]) -> Vec<TimeRange> {
+        values
+            .iter()
+            .map(|&(start, end)| TimeRange { start, end })
+            .collect()
+    }
+
+    #[test]
+    fn parse_video_packets_sorts_frames_and_keyframes() {
+        let output = "0.000000,K__\n0.066733,___\n0.033367,___\nN/A,K_\n2.002000,K_\n";
+        let packets = parse_video_packets(output);
+        assert_eq!(packets.keyframes, vec![0.0, 2.002]);
+        assert_eq!(packets.frames, vec![0.0, 0.033367, 0.066733, 2.002]);
+    }
+
+    #[test]
+    fn batch_trim_spec_resolves_per_file_ranges() {
+        let spec = BatchTrimSpec {
+            trim_start: 5.0,
+            trim_end: 10.0,
+            keep_range: None,
+            remove_silence: None,
+            auto_trim: None,
+            normalize_loudness: None,
+        };
+        assert_eq!(spec.resolve(60.0), Ok((5.0, 50.0)));
+        assert!(spec.resolve(14.0).is_err());
+
+        let item = BatchTrimOverride {
+            path: "b.mp4".into(),
+            trim_start: None,
+            trim_end: Some(0.0),
+            keep_range: None,
+        };
+        assert_eq!(spec.with_override(&item).resolve(60.0), Ok((5.0, 60.0)));
+
+        let keep = BatchTrimSpec {
+            keep_range: Some(TimeRange {
+                start: 30.0,
+                end: 90.0,
+            }),
+            ..spec
+        };
+        assert_eq!(keep.resolve(60.0), Ok((30.0, 60.0)));
+        assert!(keep.resolve(20.0).is_err());
+        assert!(BatchTrimSpec::default().validate().is_err());
+        let silence_only = BatchTrimSpec {
+            remove_silence: Some(SilenceOptions::default()),
+            ..BatchTrimSpec::default()
+        };
+        assert!(silence_only.validate().is_ok());
+        assert_eq!(silence_only.resolve(60.0), Ok((0.0, 60.0)));
+        let loudness_only = BatchTrimSpec {
+            normalize_loudness: Some(LoudnessTarget::default()),
+            ..BatchTrimSpec::default()
+        };
+        assert!(loudness_only.validate().is_ok());
+    }
+
+    #[test]
+    fn split_ranges_cover_whole_video_and_ignore_edge_points() {
+        assert_eq!(
+            split_ranges(&[30.0, 10.0, 0.0, 60.0, 10.01], 60.0),
+            vec![
+                TimeRange {
+                    start: 0.0,
+                    end: 10.0
+                },
+                TimeRange {
+                    start: 10.0,
+                    end: 30.0
+                },
+                TimeRange {
+                    start: 30.0,
+                    end: 60.0
+                },
+            ]
+        );
+        assert_eq!(split_ranges(&[], 5.0).len(), 1);
+    }
+
+    #[test]
+    fn align_to_keyframes_snaps_back_or_moves_past_long_gops() {
+        let keyframes = [0.0, 4.0, 8.0, 20.0, 24.0];
+        assert_eq!(
+            align_to_keyframes(&[5.0, 10.0, 15.0, 21.0], &keyframes),
+            vec![4.0, 8.0, 20.0, 24.0]
+        );
+        assert_eq!(align_to_keyframes(&[2.0], &keyframes), vec![4.0]);
+        assert_eq!(align_to_keyframes(&[2.5], &[]), vec![2.5]);
+        assert_eq!(evenly_spaced_points(30.0, 75.0), Ok(vec![30.0, 60.0]));
+        assert!(evenly_spaced_points(0.01, 3600.0).is_err());
+    }
+
+    #[test]
+    fn snap_to_keyframe_uses_last_keyframe_not_after_start() {
+        let keyframes = [0.0, 2.0, 4.0];
+        assert_eq!(snap_to_keyframe(&keyframes, 3.5), Some(2.0));
+        assert_eq!(snap_to_keyframe(&keyframes, 3.995), Some(4.0));
+        assert_eq!(snap_to_keyframe(&keyframes, 10.0), Some(4.0));
+        assert_eq!(snap_to_keyframe(&[1.0], 0.5), None);
+    }
+
+    /// 源视频和重新编码的片段用不同的 x264 参数，拼接后复制的中段必须能正常解码
+    /// cargo test smart_cut_join -- --ignored（需要 PATH 中带 libx264 的 ffmpeg）
+    #[test]
+    #[ignore]
+    fn smart_cut_join_decodes_across_encoders() {
+        let temp_dir = TestDir::new();
+        let ffmpeg = Path::new("ffmpeg");
+        let source = temp_dir.path().join("source.mp4");
+        let status = Command::new(ffmpeg)
+            .args(["-y", "-v", "error", "-f", "lavfi", "-i"])
+            .arg("testsrc2=size=320x240:rate=25")
+            .args(["-t", "6", "-c:v", "libx264", "-profile:v", "baseline"])
+            .args(["-x264-params", "keyint=50:min-keyint=50:scenecut=0"])
+            .args(["-pix_fmt", "yuv420p"])
+            .arg(&source)
+            .status()
+            .expect("ffmpeg should run");
+        assert!(status.success());
+
+        // 源视频为 baseline，重新编码用 high，两者的 SPS 必然不同
+        let params = SourceVideoParams {
+            codec_name: Some("h264".into()),
+            profile: Some("High".into()),
+            pix_fmt: Some("yuv420p".into()),
+            time_base: Some("1/12800".into()),
+        };
+        let output = temp_dir.path().join("output.mp4");
+        let output = output.to_string_lossy().to_string();
+        let (segment_ext, copy_args) = smart_cut_segment_format(Some("h264"), &output);
+        let encoder_args = smart_cut_encoder_args(&params, &segment_ext).unwrap();
+        let pieces = plan_smart_cut(&[0.0, 2.0, 4.0], 1.0, 5.0);
+        assert_eq!(pieces.len(), 3);
+
+        let cancelled = AtomicBool::new(false);
+        let mut segments = Vec::new();
+        for (index, piece) in pieces.iter().enumerate() {
+            let segment = temp_dir
+                .path()
+                .join(format!("part_{}.{}", index, segment_ext));
+            run_smart_cut_piece(
+                ffmpeg,
+                &source.to_string_lossy(),
+                &segment.to_string_lossy(),
+                piece,
+                if piece.copy {
+                    &copy_args
+                } else {
+                    &encoder_args
+                },
+                &cancelled,
+                |_| {},
+            )
+            .expect("piece should export");
+            segments.push(segment);
+        }
+        let list_path = temp_dir.path().join("segments.txt");
+        write_concat_list(&list_path, &segments).unwrap();
+        run_concat(ffmpeg, &list_path, &output, 4.0, None, &cancelled, |_| {})
+            .expect("concat should succeed");
+
+        let decoded = Command::new(ffmpeg)
+            .args(["-v", "error", "-xerror", "-i", &output])
+            .args(["-f", "null", "-"])
+            .output()
+            .expect("ffmpeg should run");
+        let errors = String::from_utf8_lossy(&decoded.stderr);
+        assert!(
+            decoded.status.success() && errors.trim().is_empty(),
+            "{}",
+            errors
+        );
+    }
+
+    #[test]
+    fn plan_smart_cut_copies_between_boundary_keyframes() {
+        let keyframes = [0.0, 2.0, 4.0, 6.0, 8.0];
+        assert_eq!(
+            plan_smart_cut(&keyframes, 1.5, 7.0),
+            vec![
+                SmartCutPiece {
+                    start: 1.5,
+                    end: 2.0,
+                    copy: false
+                },
+                SmartCutPiece {
+                    start: 2.0,
+                    end: 6.0,
+                    copy: true
+                },
+                SmartCutPiece {
+                    start: 6.0,
+                    end: 7.0,
+                    copy: false
+                },
+            ]
+        );
+        // 切点恰好落在关键帧上时不需要重新编码
+        assert_eq!(
+            plan_smart_cut(&keyframes, 2.0, 6.0),
+            vec![SmartCutPiece {
+                start: 2.0,
+                end: 6.0,
+                copy: true
+            }]
+        );
+        // 范围内没有两个关键帧时整段重新编码
+        assert_eq!(
+            plan_smart_cut(&keyframes, 2.5, 3.5),
+            vec![SmartCutPiece {
+                start: 2.5,
+                end: 3.5,
+                copy: false
+            }]
+        );
+    }
+
+    #[test]
+    fn resolve_keep_ranges_sorts_merges_and_clamps() {
+        let keep = resolve_keep_ranges(
+            &ranges(&[(50.0, 70.0), (10.0, 20.0), (15.0, 30.0), (90.0, 200.0)]),
+            SegmentMode::Keep,
+            100.0,
+        )
+        .expect("ranges should resolve");
+
+        assert_eq!(keep, ranges(&[(10.0, 30.0), (50.0, 70.0), (90.0, 100.0)]));
+    }
+
+    #[test]
+    fn resolve_keep_ranges_inverts_removed_ranges() {
+        let keep = resolve_keep_ranges(
+            &ranges(&[(0.0, 5.0), (40.0, 45.0), (20.0, 30.0)]),
+            SegmentMode::Remove,
+            60.0,
+        )
+        .expect("ranges should resolve");
+
+        assert_eq!(keep, ranges(&[(5.0, 20.0), (30.0, 40.0), (45.0, 60.0)]));
+        assert!(resolve_keep_ranges(&ranges(&[(0.0, 60.0)]), SegmentMode::Remove, 60.0).is_err());
+        assert!(resolve_keep_ranges(&ranges(&[(30.0, 10.0)]), SegmentMode::Keep, 60.0).is_err());
+    }
+
     #[test]
     fn create_unique_output_path_avoids_existing_and_input_paths() {
         let temp_dir = TestDir::new();
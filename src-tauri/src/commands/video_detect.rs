@@ -0,0 +1,498 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use std::sync::Mutex;
+
+use super::ffmpeg_utils::run_ffmpeg_analysis;
+use super::video::TimeRange;
+
+// 短于该时长的保留片段直接丢弃
+const MIN_KEEP_DURATION: f64 = 0.05;
+// 分析画面前先缩小，速度快很多，结果差别不大
+const ANALYSIS_WIDTH: u32 = 320;
+// 黑屏/静止区间与视频开头结尾相距不超过该值（秒）时视为紧贴边缘
+const EDGE_TOLERANCE: f64 = 0.1;
+
+/// 静音检测参数，所有字段均可省略
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct SilenceOptions {
+    /// 低于该音量（dB）视为静音
+    pub noise_db: f64,
+    /// 持续超过该时长（秒）才算一段静音
+    pub min_duration: f64,
+    /// 删除静音时两侧各保留的时长（秒），避免吞掉语音的开头和结尾
+    pub padding: f64,
+}
+
+impl Default for SilenceOptions {
+    fn default() -> Self {
+        Self {
+            noise_db: -35.0,
+            min_duration: 0.5,
+            padding: 0.2,
+        }
+    }
+}
+
+impl SilenceOptions {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(-90.0..0.0).contains(&self.noise_db) {
+            return Err("静音阈值应在 -90dB 到 0dB 之间".into());
+        }
+        if self.min_duration <= 0.0 {
+            return Err("最短静音时长必须大于 0".into());
+        }
+        if self.padding < 0.0 {
+            return Err("保留的边距不能为负数".into());
+        }
+        Ok(())
+    }
+}
+
+/// 用 silencedetect 检测第一条音轨中的静音区间，只分析前 `duration` 秒
+pub fn detect_silence<F>(
+    ffmpeg: &Path,
+    input: &str,
+    options: &SilenceOptions,
+    duration: f64,
+    cancelled: &AtomicBool,
+    process: &Mutex<Option<u32>>,
+    on_progress: F,
+) -> Result<Vec<TimeRange>, String>
+where
+    F: FnMut(f64),
+{
+    options.validate()?;
+    let args = vec![
+        "-hide_banner".to_string(),
+        "-nostats".to_string(),
+        "-t".to_string(),
+        format!("{}", duration),
+        "-i".to_string(),
+        input.to_string(),
+        "-map".to_string(),
+        "0:a:0".to_string(),
+        "-af".to_string(),
+        format!(
+            "silencedetect=noise={}dB:d={}",
+            options.noise_db, options.min_duration
+        ),
+    ];
+
+    let log = run_ffmpeg_analysis(ffmpeg, &args, duration, cancelled, process, on_progress)
+        .map_err(|error| {
+            if error.contains("matches no streams") {
+                "视频没有音轨，无法检测静音".to_string()
+            } else {
+                error
+            }
+        })?;
+    Ok(parse_silencedetect(&log, duration))
+}
+
+/// 解析 silencedetect 日志，文件末尾未结束的静音以 `duration` 收尾
+fn parse_silencedetect(log: &str, duration: f64) -> Vec<TimeRange> {
+    let mut intervals = Vec::new();
+    let mut pending_start = None;
+    for line in log.lines() {
+        if let Some(value) = log_value(line, "silence_start:") {
+            pending_start = Some(value.max(0.0));
+        } else if let Some(end) = log_value(line, "silence_end:") {
+            if let Some(start) = pending_start.take() {
+                if end > start {
+                    intervals.push(TimeRange { start, end });
+                }
+            }
+        }
+    }
+    if let Some(start) = pending_start {
+        if duration > start {
+            intervals.push(TimeRange {
+                start,
+                end: duration,
+            });
+        }
+    }
+    intervals
+}
+
+/// 黑屏和静止画面检测参数，所有字段均可省略
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct BlackFreezeOptions {
+    pub detect_black: bool,
+    /// 黑屏持续超过该时长（秒）才算一段
+    pub black_min_duration: f64,
+    /// 亮度低于该比例（0-1）的像素视为黑色
+    pub black_pixel_threshold: f64,
+    pub detect_freeze: bool,
+    /// 画面变化低于该噪声（dB）视为静止
+    pub freeze_noise_db: f64,
+    /// 静止持续超过该时长（秒）才算一段
+    pub freeze_min_duration: f64,
+}
+
+impl Default for BlackFreezeOptions {
+    fn default() -> Self {
+        Self {
+            detect_black: true,
+            black_min_duration: 0.5,
+            black_pixel_threshold: 0.1,
+            detect_freeze: true,
+            freeze_noise_db: -60.0,
+            freeze_min_duration: 2.0,
+        }
+    }
+}
+
+impl BlackFreezeOptions {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.detect_black && !self.detect_freeze {
+            return Err("请至少选择检测黑屏或静止画面中的一项".into());
+        }
+        if self.black_min_duration <= 0.0 || self.freeze_min_duration <= 0.0 {
+            return Err("最短持续时长必须大于 0".into());
+        }
+        if !(0.0..=1.0).contains(&self.black_pixel_threshold) {
+            return Err("黑色像素阈值应在 0 到 1 之间".into());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct BlackFreezeIntervals {
+    pub black: Vec<TimeRange>,
+    pub frozen: Vec<TimeRange>,
+}
+
+impl BlackFreezeIntervals {
+    /// 去掉紧贴开头和结尾的黑屏/静止区间后剩下的范围，相邻区间会连在一起计算
+    ///
+    /// 整段都是黑屏或静止时返回的结束时间不大于开始时间。
+    pub fn trimmed_range(&self, start: f64, end: f64) -> (f64, f64) {
+        let mut intervals: Vec<TimeRange> =
+            self.black.iter().chain(&self.frozen).copied().collect();
+        intervals.sort_by(|a, b| a.start.total_cmp(&b.start));
+
+        let mut trimmed_start = start;
+        for range in &intervals {
+            if range.start <= trimmed_start + EDGE_TOLERANCE {
+                trimmed_start = trimmed_start.max(range.end);
+            }
+        }
+        intervals.sort_by(|a, b| b.end.total_cmp(&a.end));
+        let mut trimmed_end = end;
+        for range in &intervals {
+            if range.end >= trimmed_end - EDGE_TOLERANCE {
+                trimmed_end = trimmed_end.min(range.start);
+            }
+        }
+        (trimmed_start, trimmed_end)
+    }
+}
+
+/// 用 blackdetect/freezedetect 检测黑屏和静止画面，只分析前 `duration` 秒
+pub fn detect_black_freeze<F>(
+    ffmpeg: &Path,
+    input: &str,
+    options: &BlackFreezeOptions,
+    duration: f64,
+    cancelled: &AtomicBool,
+    process: &Mutex<Option<u32>>,
+    on_progress: F,
+) -> Result<BlackFreezeIntervals, String>
+where
+    F: FnMut(f64),
+{
+    options.validate()?;
+    let mut filters = vec![format!("scale={}:-2", ANALYSIS_WIDTH)];
+    if options.detect_black {
+        filters.push(format!(
+            "blackdetect=d={}:pix_th={}",
+            options.black_min_duration, options.black_pixel_threshold
+        ));
+    }
+    if options.detect_freeze {
+        filters.push(format!(
+            "freezedetect=n={}dB:d={}",
+            options.freeze_noise_db, options.freeze_min_duration
+        ));
+    }
+    let args = vec![
+        "-hide_banner".to_string(),
+        "-nostats".to_string(),
+        "-t".to_string(),
+        format!("{}", duration),
+        "-i".to_string(),
+        input.to_string(),
+        "-map".to_string(),
+        "0:v:0".to_string(),
+        "-vf".to_string(),
+        filters.join(","),
+    ];
+
+    let log = run_ffmpeg_analysis(ffmpeg, &args, duration, cancelled, process, on_progress)?;
+    Ok(parse_black_freeze(&log, duration))
+}
+
+/// blackdetect 每段输出一行 `black_start:… black_end:…`；
+/// freezedetect 分行输出 `freeze_start` 和 `freeze_end`，结尾未结束的静止以 `duration` 收尾
+fn parse_black_freeze(log: &str, duration: f64) -> BlackFreezeIntervals {
+    let mut intervals = BlackFreezeIntervals::default();
+    let mut freeze_start = None;
+    for line in log.lines() {
+        if let (Some(start), Some(end)) = (
+            log_value(line, "black_start:"),
+            log_value(line, "black_end:"),
+        ) {
+            intervals.black.push(TimeRange { start, end });
+        } else if let Some(start) = log_value(line, "freeze_start:") {
+            freeze_start = Some(start);
+        } else if let Some(end) = log_value(line, "freeze_end:") {
+            if let Some(start) = freeze_start.take() {
+                intervals.frozen.push(TimeRange { start, end });
+            }
+        }
+    }
+    if let Some(start) = freeze_start {
+        if duration > start {
+            intervals.frozen.push(TimeRange {
+                start,
+                end: duration,
+            });
+        }
+    }
+    intervals
+}
+
+/// 一次场景切换，`score` 为 0-1 的画面差异分数
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct SceneChange {
+    pub time: f64,
+    pub score: f64,
+    /// 切换后第一帧的缩略图（base64），生成失败时为空
+    pub thumbnail: Option<String>,
+}
+
+/// 用 scene 分数检测画面切换，`threshold` 越小越敏感，只分析前 `duration` 秒
+pub fn detect_scenes<F>(
+    ffmpeg: &Path,
+    input: &str,
+    threshold: f64,
+    duration: f64,
+    cancelled: &AtomicBool,
+    process: &Mutex<Option<u32>>,
+    on_progress: F,
+) -> Result<Vec<SceneChange>, String>
+where
+    F: FnMut(f64),
+{
+    if !(threshold > 0.0 && threshold < 1.0) {
+        return Err("场景检测灵敏度应在 0 到 1 之间".into());
+    }
+    let args = vec![
+        "-hide_banner".to_string(),
+        "-nostats".to_string(),
+        "-t".to_string(),
+        format!("{}", duration),
+        "-i".to_string(),
+        input.to_string(),
+        "-map".to_string(),
+        "0:v:0".to_string(),
+        "-vf".to_string(),
+        format!(
+            "scale={}:-2,select='gt(scene,{})',metadata=print",
+            ANALYSIS_WIDTH, threshold
+        ),
+    ];
+
+    let log = run_ffmpeg_analysis(ffmpeg, &args, duration, cancelled, process, on_progress)?;
+    Ok(parse_scene_metadata(&log))
+}
+
+/// metadata=print 先输出一行 `pts_time:`，随后是该帧的 `lavfi.scene_score=`
+fn parse_scene_metadata(log: &str) -> Vec<SceneChange> {
+    let mut scenes = Vec::new();
+    let mut frame_time = None;
+    for line in log.lines() {
+        if let Some(time) = log_value(line, "pts_time:") {
+            frame_time = Some(time);
+        } else if let Some((_, score)) = line.split_once("lavfi.scene_score=") {
+            if let (Some(time), Ok(score)) = (frame_time.take(), score.trim().parse::<f64>()) {
+                scenes.push(SceneChange {
+                    time,
+                    score,
+                    thumbnail: None,
+                });
+            }
+        }
+    }
+    scenes
+}
+
+/// 读取日志中 `key` 后面的数值，例如 `silence_end: 15.678 | silence_duration: 3.3`
+fn log_value(line: &str, key: &str) -> Option<f64> {
+    let (_, rest) = line.split_once(key)?;
+    rest.split_whitespace().next()?.parse::<f64>().ok()
+}
+
+/// 在 `[start, end]` 内去掉检测到的区间，区间两侧各收缩 `padding`，返回要保留的片段
+pub fn ranges_without(
+    intervals: &[TimeRange],
+    start: f64,
+    end: f64,
+    padding: f64,
+) -> Vec<TimeRange> {
+    let mut removed: Vec<TimeRange> = intervals
+        .iter()
+        .map(|range| TimeRange {
+            // 落在边界上的区间直接删到边界，不留边距
+            start: if range.start <= start {
+                start
+            } else {
+                range.start + padding
+            },
+            end: if range.end >= end {
+                end
+            } else {
+                range.end - padding
+            },
+        })
+        .filter(|range| range.end - range.start >= MIN_KEEP_DURATION)
+        .collect();
+    removed.sort_by(|a, b| a.start.total_cmp(&b.start));
+
+    let mut keep = Vec::with_capacity(removed.len() + 1);
+    let mut cursor = start;
+    for range in removed {
+        if range.start - cursor >= MIN_KEEP_DURATION {
+            keep.push(TimeRange {
+                start: cursor,
+                end: range.start,
+            });
+        }
+        cursor = cursor.max(range.end);
+    }
+    if end - cursor >= MIN_KEEP_DURATION {
+        keep.push(TimeRange { start: cursor, end });
+    }
+    keep
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_silencedetect_pairs_starts_and_ends() {
+        let log = "\
+[silencedetect @ 0x600] silence_start: -0.01
+[silencedetect @ 0x600] silence_end: 1.5 | silence_duration: 1.51
+size=N/A time=00:00:10.00 bitrate=N/A
+[silencedetect @ 0x600] silence_start: 8.25
+";
+        assert_eq!(
+            parse_silencedetect(log, 10.0),
+            vec![
+                TimeRange {
+                    start: 0.0,
+                    end: 1.5
+                },
+                TimeRange {
+                    start: 8.25,
+                    end: 10.0
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_scene_metadata_reads_time_and_score() {
+        let log = "\
+[Parsed_metadata_2 @ 0x600] frame:0    pts:6006   pts_time:6.006
+[Parsed_metadata_2 @ 0x600] lavfi.scene_score=0.512345
+[Parsed_metadata_2 @ 0x600] frame:1    pts:12012  pts_time:12.012
+[Parsed_metadata_2 @ 0x600] lavfi.scene_score=0.8
+";
+        let scenes = parse_scene_metadata(log);
+        assert_eq!(scenes.len(), 2);
+        assert_eq!((scenes[0].time, scenes[0].score), (6.006, 0.512345));
+        assert_eq!((scenes[1].time, scenes[1].score), (12.012, 0.8));
+    }
+
+    #[test]
+    fn parse_black_freeze_reads_both_filters() {
+        let log = "\
+[blackdetect @ 0x600] black_start:0 black_end:1.5 black_duration:1.5
+[freezedetect @ 0x700] lavfi.freezedetect.freeze_start: 1.5
+[freezedetect @ 0x700] lavfi.freezedetect.freeze_duration: 2.5
+[freezedetect @ 0x700] lavfi.freezedetect.freeze_end: 4
+[freezedetect @ 0x700] lavfi.freezedetect.freeze_start: 55
+";
+        let intervals = parse_black_freeze(log, 60.0);
+        assert_eq!(
+            intervals.black,
+            vec![TimeRange {
+                start: 0.0,
+                end: 1.5
+            }]
+        );
+        assert_eq!(
+            intervals.frozen,
+            vec![
+                TimeRange {
+                    start: 1.5,
+                    end: 4.0
+                },
+                TimeRange {
+                    start: 55.0,
+                    end: 60.0
+                },
+            ]
+        );
+        // 开头的黑屏和紧接着的静止连在一起去掉，中间的区间不影响
+        assert_eq!(intervals.trimmed_range(0.0, 60.0), (4.0, 55.0));
+        assert_eq!(intervals.trimmed_range(10.0, 50.0), (10.0, 50.0));
+    }
+
+    #[test]
+    fn ranges_without_keeps_padding_around_speech() {
+        let silences = [
+            TimeRange {
+                start: 0.0,
+                end: 2.0,
+            },
+            TimeRange {
+                start: 5.0,
+                end: 5.3,
+            },
+            TimeRange {
+                start: 8.0,
+                end: 10.0,
+            },
+        ];
+        // 中间那段静音收缩后太短，不会被删除
+        assert_eq!(
+            ranges_without(&silences, 0.0, 10.0, 0.2),
+            vec![TimeRange {
+                start: 1.8,
+                end: 8.2
+            }]
+        );
+        assert_eq!(
+            ranges_without(&silences, 3.0, 9.0, 0.0),
+            vec![
+                TimeRange {
+                    start: 3.0,
+                    end: 5.0
+                },
+                TimeRange {
+                    start: 5.3,
+                    end: 8.0
+                },
+            ]
+        );
+    }
+}
@@ -1,14 +1,24 @@
 mod commands;
 
-use commands::convert::{cancel_convert, convert_video, get_file_size};
+use commands::convert::{cancel_convert, convert_video, extract_audio, get_file_size};
 use commands::dedup::{cancel_dedup, delete_files, find_duplicates, get_file_thumbnail};
 use commands::file_stats::{cancel_file_stats, scan_directory};
+use commands::file_stats_cleanup::apply_cleanup_action;
+use commands::file_stats_export::export_file_stats;
 use commands::logger::{get_log_path, get_recent_logs};
+use commands::loudness::{analyze_loudness, cancel_loudness_analysis};
+use commands::media_info::inspect_media;
+use commands::merge::{cancel_merge, merge_videos};
+use commands::storage_growth::{
+    get_storage_growth_folders, get_storage_growth_series, set_storage_growth_folders,
+};
 use commands::system::open_file_path;
 use commands::video::{
     batch_trim_videos, cancel_batch_video_trim, cancel_video_cut, collect_batch_video_files,
-    cut_video, cut_video_precise, generate_preview_frame, generate_timeline_frames,
-    get_video_duration, get_video_info,
+    cut_video, cut_video_precise, cut_video_segments, cut_video_smart, detect_black_frozen_frames,
+    detect_scene_changes, detect_silence_intervals, generate_preview_frame,
+    generate_timeline_frames, get_keyframe_index, get_video_duration, get_video_info,
+    remove_silence, split_video, split_video_at_points,
 };
 use commands::watermark::{batch_remove_watermark, get_image_info, remove_watermark};
 
@@ -22,19 +32,36 @@ pub fn run() {
             // 初始化文件日志
             commands::logger::init_logger(app.handle());
             commands::logger::log_info("小文喵启动完成");
+            // 定时统计文件夹空间占用
+            commands::storage_growth::start_scheduler(app.handle().clone());
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             scan_directory,
             cancel_file_stats,
+            export_file_stats,
+            apply_cleanup_action,
+            get_storage_growth_folders,
+            set_storage_growth_folders,
+            get_storage_growth_series,
             find_duplicates,
             delete_files,
             get_file_thumbnail,
             cancel_dedup,
             get_video_duration,
             get_video_info,
+            inspect_media,
             cut_video,
             cut_video_precise,
+            cut_video_segments,
+            cut_video_smart,
+            get_keyframe_index,
+            detect_silence_intervals,
+            remove_silence,
+            detect_black_frozen_frames,
+            detect_scene_changes,
+            split_video_at_points,
+            split_video,
             generate_preview_frame,
             generate_timeline_frames,
             cancel_video_cut,
@@ -43,6 +70,11 @@ pub fn run() {
             cancel_batch_video_trim,
             convert_video,
             cancel_convert,
+            extract_audio,
+            analyze_loudness,
+            cancel_loudness_analysis,
+            merge_videos,
+            cancel_merge,
             get_image_info,
             remove_watermark,
             batch_remove_watermark,
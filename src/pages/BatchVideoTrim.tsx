@@ -482,8 +482,10 @@ export default function BatchVideoTrim({ active = true }: { active?: boolean })
       const response = await invoke<BatchTrimResult>("batch_trim_videos", {
         taskId,
         paths: files.map((item) => item.path),
-        trimStart: trimTime,
-        preciseMode,
+        options: {
+          trim_start: trimTime,
+          precise_mode: preciseMode,
+        },
         outputMode,
         outputDir: outputMode === "directory" ? outputDir : null,
         suffix,